@@ -4,16 +4,52 @@
 //!   1. Built-in defaults compiled into the binary so Codex works out-of-the-box.
 //!   2. User-defined entries inside `~/.codex/config.toml` under the `model_providers`
 //!      key. These override or extend the defaults at runtime.
+//!
+//! Each entry may also set `proxy` and `connect_timeout_ms` to route that
+//! provider's traffic through a corporate proxy or tune how quickly a dead
+//! connection is abandoned, independent of the other providers in the table.
+//!
+//! `base_url` and the wire-api-specific path suffix may contain `{model}`
+//! (always resolved from the request's model id) plus any custom tokens
+//! declared in `url_params`, e.g. `{account_id}` or `{deployment}`, so
+//! deployment-style and account-scoped endpoints don't need bespoke code.
+//!
+//! Providers also honor convention-based environment overrides keyed off
+//! their `model_providers` id `<id>`, where `<ID>` is `<id>` upper-cased with
+//! any non-alphanumeric byte mapped to `_`: `CODEX_<ID>_API_KEY` supplies the
+//! API key for every provider, built-in or user-defined, while
+//! `CODEX_<ID>_BASE_URL`/`CODEX_<ID>_API_BASE` override the base URL of the
+//! built-in providers only; a user-defined provider's base URL comes from its
+//! `config.toml` entry alone. Precedence, highest first:
+//!   1. An explicit `base_url`/`env_key` set on the provider's
+//!      `model_providers` entry in `config.toml`.
+//!   2. The convention-based `CODEX_<ID>_*` variable (or, for built-ins that
+//!      predate this convention, their legacy variable like
+//!      `OPENAI_BASE_URL`).
+//!   3. The provider's hard-coded built-in default, if any.
+//! See [`ModelProviderInfo::api_key`] and the private `base_url_override`
+//! helper for where this is implemented.
 
 use crate::CodexAuth;
 use crate::default_client::CodexHttpClient;
 use crate::default_client::CodexRequestBuilder;
 use codex_app_server_protocol::AuthMode;
+use hmac::Hmac;
+use hmac::Mac;
+use jsonwebtoken::encode as jwt_encode;
+use jsonwebtoken::Algorithm;
+use jsonwebtoken::EncodingKey;
+use jsonwebtoken::Header;
+use rand::Rng;
 use serde::Deserialize;
 use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
 use std::collections::HashMap;
-use std::env::VarError;
+use std::sync::Mutex;
+use std::sync::OnceLock;
 use std::time::Duration;
+use std::time::SystemTime;
 
 use crate::error::EnvVarError;
 const DEFAULT_STREAM_IDLE_TIMEOUT_MS: u64 = 300_000;
@@ -47,6 +83,116 @@ pub enum WireApi {
     /// Anthropic Messages API (Claude) at `/v1/messages`.
     #[serde(rename = "anthropic_messages")]
     AnthropicMessages,
+
+    /// Mistral's fill-in-the-middle completions API at `/fim/completions`.
+    /// Unlike the other wire APIs, requests carry a `prompt`/`suffix` pair
+    /// instead of a message list, making this suitable for driving genuine
+    /// inline-completion backends rather than conversational ones.
+    #[serde(rename = "mistral_fim")]
+    MistralFim,
+
+    /// Amazon Bedrock's `invoke-with-response-stream` API. Bedrock rejects
+    /// the ordinary `Authorization: Bearer` header; requests must instead be
+    /// signed with AWS Signature V4 via [`SigningScheme::SigV4`].
+    #[serde(rename = "bedrock")]
+    Bedrock,
+}
+
+/// Alternative request-signing scheme for providers that cannot use a plain
+/// bearer token. When a provider sets [`ModelProviderInfo::signing`],
+/// `create_request_builder` signs the request per this scheme instead of
+/// attaching `Authorization: Bearer`.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SigningScheme {
+    /// AWS Signature Version 4, as required by Bedrock and other AWS
+    /// services. `service` is the AWS service name (e.g. `bedrock`) and
+    /// `region` is the AWS region (e.g. `us-east-1`).
+    SigV4 { service: String, region: String },
+}
+
+/// Authentication scheme a provider uses in [`create_request_builder`].
+///
+/// [`create_request_builder`]: ModelProviderInfo::create_request_builder
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProviderAuth {
+    /// Static API key/bearer token via `env_key`/`experimental_bearer_token`
+    /// (the default for every provider except Vertex AI).
+    #[default]
+    ApiKey,
+
+    /// Google Cloud service-account (JWT-bearer) authentication, as required
+    /// by Vertex AI. `create_request_builder` exchanges the key file for a
+    /// short-lived OAuth2 access token (see
+    /// [`ModelProviderInfo::google_vertex_bearer_token`]) instead of reading
+    /// `env_key` directly.
+    GoogleServiceAccount {
+        /// Path to the service-account key file. When unset, falls back to
+        /// the standard `GOOGLE_APPLICATION_CREDENTIALS` environment
+        /// variable, so two `model_providers` entries can each point at a
+        /// different service-account file (and so a different GCP project)
+        /// without fighting over one process-wide env var.
+        key_path: Option<String>,
+
+        /// OAuth2 scopes to request for the minted access token. Empty
+        /// defaults to the Vertex AI `cloud-platform` scope.
+        #[serde(default)]
+        scopes: Vec<String>,
+    },
+}
+
+/// How to pick one key out of a comma-separated [`ModelProviderInfo::env_key`]
+/// value when it resolves to more than one, e.g.
+/// `ANTHROPIC_API_KEY="sk-a, sk-b, sk-c"`. Spreading requests across keys
+/// this way helps dodge per-key rate limits. Irrelevant when the env var
+/// holds exactly one key.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum KeySelectionMode {
+    /// Pick a key uniformly at random for each request.
+    #[default]
+    Random,
+    /// Cycle through the keys in order, one per request, via a small
+    /// per-provider atomic counter (see `next_round_robin_index`).
+    RoundRobin,
+}
+
+/// Catalog id a [`ModelInfo`] entry can use in place of a real model id to
+/// act as a passthrough default for any model the entry doesn't exactly
+/// match. Lets a provider accept a newly released model Codex doesn't know
+/// about yet with sane limits instead of rejecting it outright; see
+/// [`ModelProviderInfo::model_info`].
+pub const WILDCARD_MODEL_ID: &str = "*";
+
+/// A single model entry in a provider's [`ModelProviderInfo::models`]
+/// catalog, giving Codex enough metadata to make context-window and
+/// truncation decisions without hard-coding per-model limits.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct ModelInfo {
+    /// Model identifier as accepted by the provider's API, e.g. `gpt-4o` or
+    /// `claude-opus-4-1`, or [`WILDCARD_MODEL_ID`] to act as this catalog's
+    /// fallback entry. Matched against the `model` Codex is configured to
+    /// use when looking up catalog entries.
+    pub id: String,
+
+    /// Maximum number of input (prompt + history) tokens this model accepts.
+    pub max_input_tokens: Option<u64>,
+
+    /// Maximum number of tokens this model can generate in a single response.
+    pub max_output_tokens: Option<u64>,
+
+    /// Whether this model supports streaming responses. Unset means unknown
+    /// rather than false.
+    pub supports_streaming: Option<bool>,
+
+    /// Whether this model supports tool/function calling. Unset means
+    /// unknown rather than false.
+    pub supports_tools: Option<bool>,
+
+    /// Whether this model supports image inputs. Unset means unknown rather
+    /// than false.
+    pub supports_vision: Option<bool>,
 }
 
 /// Serializable representation of a provider definition.
@@ -101,6 +247,49 @@ pub struct ModelProviderInfo {
     /// and API key (if needed) comes from the "env_key" environment variable.
     #[serde(default)]
     pub requires_openai_auth: bool,
+
+    /// Authentication scheme this provider requires. Defaults to
+    /// [`ProviderAuth::ApiKey`] (a static `env_key` value); Vertex AI, unlike
+    /// the public Generative Language API, sets
+    /// [`ProviderAuth::GoogleServiceAccount`] instead.
+    #[serde(default)]
+    pub auth: ProviderAuth,
+
+    /// Optional HTTP/SOCKS5 proxy URL (e.g. `socks5://127.0.0.1:1080` or
+    /// `https://proxy.example.com:8443`) to route requests to this provider
+    /// through. When unset, falls back to the `HTTPS_PROXY`/`ALL_PROXY`
+    /// environment variables; see [`ModelProviderInfo::effective_proxy`].
+    pub proxy: Option<String>,
+
+    /// Connect timeout (in milliseconds) to use when establishing the TCP/TLS
+    /// connection to this provider, independent of the idle/stream timeouts
+    /// above. Applies to both unary and streaming requests.
+    pub connect_timeout_ms: Option<u64>,
+
+    /// Extra placeholder values substituted into `base_url` and the
+    /// wire-api-specific path suffix, in addition to the always-available
+    /// `{model}` token. Used to fill in things like `{account_id}` for
+    /// Cloudflare Workers AI or `{deployment}` for Azure deployment-style
+    /// paths.
+    pub url_params: Option<HashMap<String, String>>,
+
+    /// Alternative request-signing scheme for providers that cannot use a
+    /// bearer token (e.g. AWS Bedrock, which requires Signature Version 4).
+    pub signing: Option<SigningScheme>,
+
+    /// Catalog of models this provider supports, with per-model context
+    /// window and capability metadata. A user `model_providers` entry in
+    /// `config.toml` that sets this field entirely replaces the built-in
+    /// catalog for that provider id (see [`ModelProviderInfo::model_info`]
+    /// for how lookups work); entries are merged like any other
+    /// `ModelProviderInfo` field, so omitting `models` from an override
+    /// leaves the built-in catalog, if any, in place.
+    pub models: Option<Vec<ModelInfo>>,
+
+    /// How to select one key when `env_key` resolves to a comma-separated
+    /// list of API keys. Defaults to [`KeySelectionMode::Random`] when unset.
+    /// Has no effect when only a single key is configured.
+    pub key_selection: Option<KeySelectionMode>,
 }
 
 impl ModelProviderInfo {
@@ -112,15 +301,75 @@ impl ModelProviderInfo {
     ///
     /// If the provider declares an `env_key` but the variable is missing/empty, returns an [`Err`] identical to the
     /// one produced by [`ModelProviderInfo::api_key`].
+    ///
+    /// `client` is the shared default client reused across providers; when
+    /// this provider overrides [`ModelProviderInfo::effective_proxy`] or
+    /// [`ModelProviderInfo::connect_timeout`], a dedicated client with those
+    /// applied is built instead (see the private `http_client_for_request`
+    /// helper), so proxy/timeout settings can differ across
+    /// `model_providers` entries without every provider needing its own
+    /// long-lived client. This covers both unary calls and the streaming
+    /// requests that reuse the returned builder's client.
+    ///
+    /// `body` is the serialized request body. It is only consulted when the
+    /// provider declares a [`ModelProviderInfo::signing`] scheme, where the
+    /// payload hash is part of what gets signed; providers using a plain
+    /// bearer token ignore it.
+    ///
+    /// `id` is this provider's `model_providers` key (e.g. `openai`,
+    /// `my-proxy`). It is passed to [`ModelProviderInfo::api_key`], which
+    /// uses it to look up the convention-based `CODEX_<ID>_API_KEY`
+    /// environment variable fallback and to key the per-provider
+    /// [`KeySelectionMode::RoundRobin`] counter.
     pub async fn create_request_builder<'a>(
         &'a self,
         client: &'a CodexHttpClient,
         auth: &Option<CodexAuth>,
+        model: &str,
+        body: &[u8],
+        id: &str,
     ) -> crate::error::Result<CodexRequestBuilder> {
+        let scoped_client = self.http_client_for_request(client)?;
+        let client = &scoped_client;
+
+        if let Some(SigningScheme::SigV4 { service, region }) = &self.signing {
+            let url = self.get_full_url(auth, model);
+            require_resolved_url(&url)?;
+            let builder = client.post(&url);
+            let builder = self.sign_sigv4(builder, &url, service, region, body)?;
+            let resolved_api_key = self.api_key(id).ok().flatten();
+            return Ok(self.apply_http_headers(builder, resolved_api_key.as_deref()));
+        }
+
+        if let ProviderAuth::GoogleServiceAccount { key_path, scopes } = &self.auth {
+            let url = self.get_full_url(&None, model);
+            require_resolved_url(&url)?;
+            let token = self
+                .google_vertex_bearer_token(client, key_path.as_deref(), scopes)
+                .await?;
+            let builder = client.post(url).bearer_auth(token);
+            let resolved_api_key = self.api_key(id).ok().flatten();
+            return Ok(self.apply_http_headers(builder, resolved_api_key.as_deref()));
+        }
+
+        // Resolve the API key exactly once per request: both the bearer-auth
+        // header below and `apply_http_headers`'s env-header aliasing (e.g.
+        // Anthropic's `x-api-key`, Google GenAI's `x-goog-api-key`) need the
+        // *same* key, not two independent draws from `select_api_key` — with
+        // `KeySelectionMode::RoundRobin` a second draw would advance the
+        // shared counter twice per request and skip every other configured
+        // key; with `Random` the two headers could end up with different
+        // keys entirely.
+        let api_key_result = self.api_key(id);
+        let resolved_api_key = match &api_key_result {
+            Ok(key) => key.clone(),
+            Err(_) => None,
+        };
+
         let effective_auth = if let Some(secret_key) = &self.experimental_bearer_token {
             Some(CodexAuth::from_api_key(secret_key))
         } else {
-            match self.api_key() {
+            match api_key_result {
                 Ok(Some(key)) => Some(CodexAuth::from_api_key(&key)),
                 Ok(None) => auth.clone(),
                 Err(err) => {
@@ -133,7 +382,8 @@ impl ModelProviderInfo {
             }
         };
 
-        let url = self.get_full_url(&effective_auth);
+        let url = self.get_full_url(&effective_auth, model);
+        require_resolved_url(&url)?;
 
         let mut builder = client.post(url);
 
@@ -141,7 +391,129 @@ impl ModelProviderInfo {
             builder = builder.bearer_auth(auth.get_token().await?);
         }
 
-        Ok(self.apply_http_headers(builder))
+        Ok(self.apply_http_headers(builder, resolved_api_key.as_deref()))
+    }
+
+    /// Signs `builder` per AWS Signature Version 4 for `service`/`region`,
+    /// reading credentials from the standard `AWS_ACCESS_KEY_ID` /
+    /// `AWS_SECRET_ACCESS_KEY` / `AWS_SESSION_TOKEN` environment variables,
+    /// and attaches the resulting `Authorization` and `x-amz-date` (and, for
+    /// temporary credentials, `x-amz-security-token`) headers.
+    fn sign_sigv4(
+        &self,
+        mut builder: CodexRequestBuilder,
+        url: &str,
+        service: &str,
+        region: &str,
+        body: &[u8],
+    ) -> crate::error::Result<CodexRequestBuilder> {
+        let access_key = read_required_aws_env("AWS_ACCESS_KEY_ID")?;
+        let secret_key = read_required_aws_env("AWS_SECRET_ACCESS_KEY")?;
+        let session_token = std::env::var("AWS_SESSION_TOKEN")
+            .ok()
+            .filter(|v| !v.trim().is_empty());
+
+        let (host, canonical_uri, canonical_query) = split_url_for_signing(url);
+        let (date_stamp, amz_date) = amz_timestamp_now();
+        let payload_hash = sha256_hex(body);
+
+        let mut canonical_headers = vec![
+            ("host".to_string(), host),
+            ("x-amz-date".to_string(), amz_date.clone()),
+        ];
+        if let Some(token) = &session_token {
+            canonical_headers.push(("x-amz-security-token".to_string(), token.clone()));
+        }
+        canonical_headers.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let signed_headers = canonical_headers
+            .iter()
+            .map(|(k, _)| k.as_str())
+            .collect::<Vec<_>>()
+            .join(";");
+        let canonical_headers_str = canonical_headers
+            .iter()
+            .map(|(k, v)| format!("{k}:{v}\n"))
+            .collect::<String>();
+
+        let canonical_request = format!(
+            "POST\n{canonical_uri}\n{canonical_query}\n{canonical_headers_str}\n{signed_headers}\n{payload_hash}"
+        );
+
+        let credential_scope = format!("{date_stamp}/{region}/{service}/aws4_request");
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{credential_scope}\n{}",
+            sha256_hex(canonical_request.as_bytes())
+        );
+
+        let k_date = hmac_sha256(
+            format!("AWS4{secret_key}").as_bytes(),
+            date_stamp.as_bytes(),
+        );
+        let k_region = hmac_sha256(&k_date, region.as_bytes());
+        let k_service = hmac_sha256(&k_region, service.as_bytes());
+        let k_signing = hmac_sha256(&k_service, b"aws4_request");
+        let signature = hex_encode(&hmac_sha256(&k_signing, string_to_sign.as_bytes()));
+
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={access_key}/{credential_scope}, SignedHeaders={signed_headers}, Signature={signature}"
+        );
+
+        builder = builder.header("x-amz-date", amz_date);
+        if let Some(token) = session_token {
+            builder = builder.header("x-amz-security-token", token);
+        }
+        builder = builder.header("Authorization", authorization);
+
+        Ok(builder)
+    }
+
+    /// Returns a Vertex AI OAuth2 access token for this provider, minting a
+    /// new one by exchanging a self-signed JWT assertion for the
+    /// service-account key at `key_path` (falling back to
+    /// `GOOGLE_APPLICATION_CREDENTIALS` when unset) when the cached token is
+    /// missing or close to expiry (see [`google_access_token_cache`]). Only
+    /// called when [`ModelProviderInfo::auth`] is set to
+    /// [`ProviderAuth::GoogleServiceAccount`].
+    async fn google_vertex_bearer_token(
+        &self,
+        client: &CodexHttpClient,
+        key_path: Option<&str>,
+        scopes: &[String],
+    ) -> crate::error::Result<String> {
+        let key = load_google_service_account_key(key_path)?;
+
+        if let Some(token) = cached_google_access_token(&key.client_email) {
+            return Ok(token);
+        }
+
+        let assertion = build_service_account_assertion(&key, scopes)?;
+        let params = [
+            ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+            ("assertion", assertion.as_str()),
+        ];
+
+        let response = client
+            .post(&key.token_uri)
+            .form(&params)
+            .send()
+            .await
+            .map_err(|err| {
+                crate::error::CodexErr::EnvVar(EnvVarError {
+                    var: "GOOGLE_APPLICATION_CREDENTIALS".to_string(),
+                    instructions: Some(format!("failed to reach {}: {err}", key.token_uri)),
+                })
+            })?;
+
+        let token: GoogleTokenResponse = response.json().await.map_err(|err| {
+            crate::error::CodexErr::EnvVar(EnvVarError {
+                var: "GOOGLE_APPLICATION_CREDENTIALS".to_string(),
+                instructions: Some(format!("unexpected response from {}: {err}", key.token_uri)),
+            })
+        })?;
+
+        cache_google_access_token(&key.client_email, &token.access_token, token.expires_in);
+        Ok(token.access_token)
     }
 
     fn get_query_string(&self) -> String {
@@ -157,7 +529,22 @@ impl ModelProviderInfo {
             })
     }
 
-    pub(crate) fn get_full_url(&self, auth: &Option<CodexAuth>) -> String {
+    /// Resolves `{token}` placeholders in `template` against `model` (always
+    /// bound to `{model}`) plus whatever the provider's `url_params` map
+    /// supplies (e.g. `{account_id}`, `{deployment}`). Unknown placeholders
+    /// are left untouched so callers can surface a clear error rather than
+    /// silently sending a literal `{foo}` to the wire.
+    fn substitute_url_params(&self, template: &str, model: &str) -> String {
+        let mut resolved = template.replace("{model}", model);
+        if let Some(params) = &self.url_params {
+            for (key, value) in params {
+                resolved = resolved.replace(&format!("{{{key}}}"), value);
+            }
+        }
+        resolved
+    }
+
+    pub(crate) fn get_full_url(&self, auth: &Option<CodexAuth>, model: &str) -> String {
         let default_base_url = if matches!(
             auth,
             Some(CodexAuth {
@@ -174,18 +561,19 @@ impl ModelProviderInfo {
             .base_url
             .clone()
             .unwrap_or(default_base_url.to_string());
+        let base_url = self.substitute_url_params(&base_url, model);
+
+        let suffix = match self.wire_api {
+            WireApi::Responses => "/responses".to_string(),
+            WireApi::Chat => "/chat/completions".to_string(),
+            WireApi::GoogleGenAI => "/models/{model}:streamGenerateContent".to_string(),
+            WireApi::AnthropicMessages => "/messages".to_string(),
+            WireApi::MistralFim => "/fim/completions".to_string(),
+            WireApi::Bedrock => "/model/{model}/invoke-with-response-stream".to_string(),
+        };
+        let suffix = self.substitute_url_params(&suffix, model);
 
-        match self.wire_api {
-            WireApi::Responses => format!("{base_url}/responses{query_string}"),
-            WireApi::Chat => format!("{base_url}/chat/completions{query_string}"),
-            WireApi::GoogleGenAI => {
-                // Note: Google GenAI requires the model name in the URL path.
-                // This placeholder will need to be replaced with the actual model name
-                // when the full implementation is added.
-                format!("{base_url}/models/{{model}}:streamGenerateContent{query_string}")
-            }
-            WireApi::AnthropicMessages => format!("{base_url}/messages{query_string}"),
-        }
+        format!("{base_url}{suffix}{query_string}")
     }
 
     pub(crate) fn is_azure_responses_endpoint(&self) -> bool {
@@ -206,7 +594,23 @@ impl ModelProviderInfo {
     /// Apply provider-specific HTTP headers (both static and environment-based)
     /// onto an existing [`CodexRequestBuilder`] and return the updated
     /// builder.
-    fn apply_http_headers(&self, mut builder: CodexRequestBuilder) -> CodexRequestBuilder {
+    ///
+    /// When an `env_http_headers` entry's environment variable is the same
+    /// one named by `env_key` (e.g. Google GenAI's `x-goog-api-key` or
+    /// Anthropic's `x-api-key`), its value comes from `resolved_api_key`
+    /// instead of a raw env var read, so those headers carry the same
+    /// convention-based fallback and multi-key selection as the OpenAI
+    /// bearer-token path. `resolved_api_key` must be the result of a single
+    /// upstream [`ModelProviderInfo::api_key`] call for this request (see
+    /// [`ModelProviderInfo::create_request_builder`]) rather than a fresh
+    /// call made here, so that a comma-separated, rotation-selected key
+    /// resolves to the same value as whatever bearer-auth header this
+    /// request already carries instead of drawing a second, independent key.
+    fn apply_http_headers(
+        &self,
+        mut builder: CodexRequestBuilder,
+        resolved_api_key: Option<&str>,
+    ) -> CodexRequestBuilder {
         if let Some(extra) = &self.http_headers {
             for (k, v) in extra {
                 builder = builder.header(k, v);
@@ -215,9 +619,12 @@ impl ModelProviderInfo {
 
         if let Some(env_headers) = &self.env_http_headers {
             for (header, env_var) in env_headers {
-                if let Ok(val) = std::env::var(env_var)
-                    && !val.trim().is_empty()
-                {
+                let resolved = if self.env_key.as_deref() == Some(env_var.as_str()) {
+                    resolved_api_key.map(str::to_string)
+                } else {
+                    std::env::var(env_var).ok().filter(|v| !v.trim().is_empty())
+                };
+                if let Some(val) = resolved {
                     builder = builder.header(header, val);
                 }
             }
@@ -227,27 +634,63 @@ impl ModelProviderInfo {
 
     /// If `env_key` is Some, returns the API key for this provider if present
     /// (and non-empty) in the environment. If `env_key` is required but
-    /// cannot be found, returns an error.
-    pub fn api_key(&self) -> crate::error::Result<Option<String>> {
-        match &self.env_key {
+    /// cannot be found, falls back to the convention-based
+    /// `CODEX_<ID>_API_KEY` variable (see [`convention_env_var`]) before
+    /// returning an error. If `env_key` is unset entirely, the
+    /// convention-based variable is consulted directly instead.
+    ///
+    /// The resolved value may be a comma-separated list of keys (e.g.
+    /// `"sk-a, sk-b, sk-c"`), in which case one is selected per
+    /// [`ModelProviderInfo::key_selection`] (see
+    /// [`ModelProviderInfo::select_api_key`]); a single key behaves exactly
+    /// as before multi-key support existed.
+    ///
+    /// `id` is this provider's `model_providers` key, used to build the
+    /// convention variable name; it does not need to match `self.name`.
+    pub fn api_key(&self, id: &str) -> crate::error::Result<Option<String>> {
+        let raw = match &self.env_key {
             Some(env_key) => {
-                let env_value = std::env::var(env_key);
-                env_value
-                    .and_then(|v| {
-                        if v.trim().is_empty() {
-                            Err(VarError::NotPresent)
-                        } else {
-                            Ok(Some(v))
-                        }
-                    })
-                    .map_err(|_| {
-                        crate::error::CodexErr::EnvVar(EnvVarError {
+                let env_value = std::env::var(env_key).ok().filter(|v| !v.trim().is_empty());
+                match env_value.or_else(|| convention_env_var(id, "API_KEY")) {
+                    Some(v) => v,
+                    None => {
+                        return Err(crate::error::CodexErr::EnvVar(EnvVarError {
                             var: env_key.clone(),
                             instructions: self.env_key_instructions.clone(),
-                        })
-                    })
+                        }));
+                    }
+                }
             }
-            None => Ok(None),
+            None => match convention_env_var(id, "API_KEY") {
+                Some(v) => v,
+                None => return Ok(None),
+            },
+        };
+
+        Ok(Some(self.select_api_key(id, &raw)))
+    }
+
+    /// Splits `raw` on commas, trims whitespace off each token, and drops
+    /// empty tokens. A single surviving key is returned as-is (identical to
+    /// pre-multi-key behavior); with more than one, picks per
+    /// [`ModelProviderInfo::key_selection`] (default [`KeySelectionMode::Random`]).
+    fn select_api_key(&self, id: &str, raw: &str) -> String {
+        let keys: Vec<&str> = raw
+            .split(',')
+            .map(str::trim)
+            .filter(|k| !k.is_empty())
+            .collect();
+        match keys.as_slice() {
+            [] => raw.to_string(),
+            [only] => (*only).to_string(),
+            many => match self.key_selection.unwrap_or_default() {
+                KeySelectionMode::Random => {
+                    many[rand::rng().random_range(0..many.len())].to_string()
+                }
+                KeySelectionMode::RoundRobin => {
+                    many[next_round_robin_index(id, many.len())].to_string()
+                }
+            },
         }
     }
 
@@ -271,13 +714,636 @@ impl ModelProviderInfo {
             .map(Duration::from_millis)
             .unwrap_or(Duration::from_millis(DEFAULT_STREAM_IDLE_TIMEOUT_MS))
     }
+
+    /// Effective proxy URL to use when constructing the [`CodexHttpClient`]
+    /// for this provider. Falls back to the `HTTPS_PROXY`/`ALL_PROXY`
+    /// environment variables when `proxy` is unset, matching the precedence
+    /// reqwest itself would apply to an unconfigured client.
+    pub fn effective_proxy(&self) -> Option<String> {
+        self.proxy.clone().or_else(|| {
+            std::env::var("HTTPS_PROXY")
+                .ok()
+                .filter(|v| !v.trim().is_empty())
+                .or_else(|| {
+                    std::env::var("ALL_PROXY")
+                        .ok()
+                        .filter(|v| !v.trim().is_empty())
+                })
+        })
+    }
+
+    /// Effective connect timeout for this provider, applied to both unary
+    /// and streaming requests when the [`CodexHttpClient`] is constructed.
+    pub fn connect_timeout(&self) -> Option<Duration> {
+        self.connect_timeout_ms.map(Duration::from_millis)
+    }
+
+    /// Returns the [`CodexHttpClient`] [`ModelProviderInfo::create_request_builder`]
+    /// should post through: `client` cloned as-is when this provider
+    /// overrides neither [`ModelProviderInfo::effective_proxy`] nor
+    /// [`ModelProviderInfo::connect_timeout`], or a fresh client with those
+    /// applied when it does. Without this, a `model_providers` entry's
+    /// `proxy`/`connect_timeout_ms` would parse but never actually change
+    /// how requests go out, since `client` is otherwise shared across every
+    /// provider.
+    fn http_client_for_request(
+        &self,
+        client: &CodexHttpClient,
+    ) -> crate::error::Result<CodexHttpClient> {
+        let proxy = self.effective_proxy();
+        let connect_timeout = self.connect_timeout();
+        if proxy.is_none() && connect_timeout.is_none() {
+            return Ok(client.clone());
+        }
+
+        let mut builder = reqwest::Client::builder();
+        if let Some(proxy_url) = &proxy {
+            let proxy = reqwest::Proxy::all(proxy_url).map_err(|err| {
+                crate::error::CodexErr::EnvVar(EnvVarError {
+                    var: "proxy".to_string(),
+                    instructions: Some(format!("invalid proxy URL {proxy_url}: {err}")),
+                })
+            })?;
+            builder = builder.proxy(proxy);
+        }
+        if let Some(timeout) = connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+
+        builder.build().map_err(|err| {
+            crate::error::CodexErr::EnvVar(EnvVarError {
+                var: "http_client".to_string(),
+                instructions: Some(format!(
+                    "failed to build HTTP client for provider {}: {err}",
+                    self.name
+                )),
+            })
+        })
+    }
+
+    /// Looks up `model_id` in this provider's [`ModelProviderInfo::models`]
+    /// catalog. Falls back to the catalog's [`WILDCARD_MODEL_ID`] entry, if
+    /// any, so a newly released model Codex doesn't know about yet is still
+    /// accepted with that provider's default limits rather than treated as
+    /// unsupported. Returns `None` if the provider declares no catalog, or
+    /// declares one with neither an exact match nor a wildcard entry.
+    pub fn model_info(&self, model_id: &str) -> Option<&ModelInfo> {
+        let models = self.models.as_ref()?;
+        models
+            .iter()
+            .find(|m| m.id == model_id)
+            .or_else(|| models.iter().find(|m| m.id == WILDCARD_MODEL_ID))
+    }
+
+    /// Context window for `model_id`, if known.
+    pub fn max_input_tokens(&self, model_id: &str) -> Option<u64> {
+        self.model_info(model_id)?.max_input_tokens
+    }
+
+    /// Maximum output tokens for `model_id`, if known.
+    pub fn max_output_tokens(&self, model_id: &str) -> Option<u64> {
+        self.model_info(model_id)?.max_output_tokens
+    }
+
+    /// Whether `model_id` supports tool/function calling, if known.
+    pub fn supports_tools(&self, model_id: &str) -> Option<bool> {
+        self.model_info(model_id)?.supports_tools
+    }
+
+    /// Whether `model_id` supports image inputs, if known.
+    pub fn supports_vision(&self, model_id: &str) -> Option<bool> {
+        self.model_info(model_id)?.supports_vision
+    }
+
+    /// Transcodes `request` from Codex's canonical, wire-agnostic shape into
+    /// the JSON body this provider's [`ModelProviderInfo::wire_api`] expects.
+    /// Callers build one [`CanonicalRequest`] per turn and hand it to every
+    /// configured provider instead of special-casing each wire protocol
+    /// themselves.
+    pub fn to_wire_request_body(&self, request: &CanonicalRequest) -> serde_json::Value {
+        match self.wire_api {
+            WireApi::Chat => chat_completions_request_body(request),
+            WireApi::Responses => responses_request_body(request),
+            WireApi::GoogleGenAI => google_genai_request_body(request),
+            WireApi::AnthropicMessages => anthropic_messages_request_body(request),
+            WireApi::MistralFim => mistral_fim_request_body(request),
+            WireApi::Bedrock => bedrock_request_body(request),
+        }
+    }
+
+    /// Transcodes a raw JSON response body from this provider's wire format
+    /// back into the assistant's reply text, the inverse of
+    /// [`ModelProviderInfo::to_wire_request_body`]. Returns `None` if the
+    /// expected field is missing or not a string, e.g. for a streaming chunk
+    /// that carries no text delta.
+    pub fn extract_canonical_text(&self, response: &serde_json::Value) -> Option<String> {
+        match self.wire_api {
+            WireApi::Chat => extract_chat_completions_text(response),
+            WireApi::Responses => extract_responses_text(response),
+            WireApi::GoogleGenAI => extract_google_genai_text(response),
+            WireApi::AnthropicMessages => extract_anthropic_messages_text(response),
+            WireApi::MistralFim => extract_mistral_fim_text(response),
+            WireApi::Bedrock => extract_bedrock_text(response),
+        }
+    }
+
+    /// Decodes one already-unwrapped streaming event payload (the JSON after
+    /// an SSE `data: ` prefix, or the JSON inside a Bedrock
+    /// `invoke-with-response-stream` event) from this provider's wire format
+    /// into a [`CanonicalStreamDelta`], the incremental counterpart of
+    /// [`ModelProviderInfo::extract_canonical_text`]. Returns `None` when
+    /// `data` isn't JSON or its shape isn't one this wire format's decoder
+    /// recognizes, e.g. an Anthropic `ping` event; a recognized event that
+    /// carries no text, such as a tool-call chunk, yields a delta whose
+    /// `text` is `None`.
+    ///
+    /// `data == "[DONE]"` is treated as the end-of-stream sentinel the
+    /// OpenAI-compatible Chat and Responses APIs emit; other wire formats
+    /// signal completion via a JSON field instead, handled per-variant below.
+    pub fn decode_stream_event(&self, data: &str) -> Option<CanonicalStreamDelta> {
+        if data == "[DONE]" {
+            return Some(CanonicalStreamDelta {
+                text: None,
+                done: true,
+            });
+        }
+        let value: serde_json::Value = serde_json::from_str(data).ok()?;
+        match self.wire_api {
+            WireApi::Chat | WireApi::MistralFim => decode_chat_completions_stream_event(&value),
+            WireApi::Responses => decode_responses_stream_event(&value),
+            WireApi::GoogleGenAI => Some(CanonicalStreamDelta {
+                text: extract_google_genai_text(&value),
+                done: false,
+            }),
+            WireApi::AnthropicMessages | WireApi::Bedrock => {
+                decode_anthropic_messages_stream_event(&value)
+            }
+        }
+    }
+
+    /// Sends `request` to this provider and returns the assistant's reply
+    /// text, the real send path tying together
+    /// [`ModelProviderInfo::to_wire_request_body`] (request construction),
+    /// [`ModelProviderInfo::create_request_builder`] (auth/headers/signing),
+    /// and [`ModelProviderInfo::extract_canonical_text`] /
+    /// [`ModelProviderInfo::decode_stream_event`] (response decoding).
+    ///
+    /// When `request.stream` is `false` the full response body is parsed as
+    /// JSON and handed to `extract_canonical_text`. When it's `true`, the
+    /// response body is read incrementally and each `data: ...` line is
+    /// decoded via `decode_stream_event`; the returned string is every
+    /// event's accumulated `text` up to and including the event marking
+    /// `done`.
+    pub async fn send_canonical_request(
+        &self,
+        client: &CodexHttpClient,
+        auth: &Option<CodexAuth>,
+        model: &str,
+        request: &CanonicalRequest,
+        id: &str,
+    ) -> crate::error::Result<Option<String>> {
+        let wire_body = self.to_wire_request_body(request);
+        let body_bytes = serde_json::to_vec(&wire_body).unwrap_or_default();
+
+        let builder = self
+            .create_request_builder(client, auth, model, &body_bytes, id)
+            .await?;
+
+        let mut response = builder.json(&wire_body).send().await.map_err(|err| {
+            crate::error::CodexErr::EnvVar(EnvVarError {
+                var: "MODEL_PROVIDER_REQUEST".to_string(),
+                instructions: Some(format!("request to {} failed: {err}", self.name)),
+            })
+        })?;
+
+        if !request.stream {
+            let response_json: serde_json::Value = response.json().await.map_err(|err| {
+                crate::error::CodexErr::EnvVar(EnvVarError {
+                    var: "MODEL_PROVIDER_REQUEST".to_string(),
+                    instructions: Some(format!("unexpected response from {}: {err}", self.name)),
+                })
+            })?;
+            return Ok(self.extract_canonical_text(&response_json));
+        }
+
+        let mut buffered = String::new();
+        let mut text = String::new();
+        while let Some(chunk) = response.chunk().await.map_err(|err| {
+            crate::error::CodexErr::EnvVar(EnvVarError {
+                var: "MODEL_PROVIDER_REQUEST".to_string(),
+                instructions: Some(format!("error reading stream from {}: {err}", self.name)),
+            })
+        })? {
+            buffered.push_str(&String::from_utf8_lossy(&chunk));
+            while let Some(pos) = buffered.find('\n') {
+                let line = buffered[..pos].trim_end_matches('\r').to_string();
+                buffered.drain(..=pos);
+                let Some(data) = line.strip_prefix("data:").map(str::trim_start) else {
+                    continue;
+                };
+                if let Some(delta) = self.decode_stream_event(data) {
+                    if let Some(piece) = delta.text {
+                        text.push_str(&piece);
+                    }
+                    if delta.done {
+                        return Ok(Some(text));
+                    }
+                }
+            }
+        }
+
+        Ok(Some(text))
+    }
+}
+
+/// Role of a [`CanonicalMessage`] in a [`CanonicalRequest`], independent of
+/// how any particular wire protocol spells it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CanonicalRole {
+    System,
+    User,
+    Assistant,
+}
+
+impl CanonicalRole {
+    fn as_openai_str(self) -> &'static str {
+        match self {
+            CanonicalRole::System => "system",
+            CanonicalRole::User => "user",
+            CanonicalRole::Assistant => "assistant",
+        }
+    }
+}
+
+/// One turn of conversation in Codex's canonical, wire-agnostic message
+/// format.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CanonicalMessage {
+    pub role: CanonicalRole,
+    pub content: String,
+}
+
+/// Wire-agnostic request Codex builds once per turn and transcodes into
+/// whatever shape the target provider's [`WireApi`] expects via
+/// [`ModelProviderInfo::to_wire_request_body`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CanonicalRequest {
+    pub model: String,
+    pub messages: Vec<CanonicalMessage>,
+    pub max_output_tokens: Option<u64>,
+    pub stream: bool,
+    /// Text that follows the cursor, for fill-in-the-middle completion
+    /// ([`WireApi::MistralFim`]); the conversational wire formats ignore
+    /// this field since they have no notion of completing around a cursor.
+    pub suffix: Option<String>,
+    /// Sampling temperature, passed through to wire formats that support it.
+    pub temperature: Option<f64>,
+}
+
+/// One incremental update decoded from a provider's streaming response by
+/// [`ModelProviderInfo::decode_stream_event`], the streaming counterpart of
+/// the single buffered [`ModelProviderInfo::extract_canonical_text`] call.
+/// Callers accumulate `text` across events until `done`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CanonicalStreamDelta {
+    /// Incremental text carried by this event, if any.
+    pub text: Option<String>,
+    /// Whether this event marks the end of the stream.
+    pub done: bool,
+}
+
+/// Builds an OpenAI Chat Completions request body: a flat `messages` array
+/// of `{role, content}`.
+fn chat_completions_request_body(request: &CanonicalRequest) -> serde_json::Value {
+    let messages: Vec<serde_json::Value> = request
+        .messages
+        .iter()
+        .map(|m| {
+            serde_json::json!({
+                "role": m.role.as_openai_str(),
+                "content": m.content,
+            })
+        })
+        .collect();
+
+    let mut body = serde_json::json!({
+        "model": request.model,
+        "messages": messages,
+        "stream": request.stream,
+    });
+    if let Some(max_output_tokens) = request.max_output_tokens {
+        body["max_tokens"] = serde_json::json!(max_output_tokens);
+    }
+    if let Some(temperature) = request.temperature {
+        body["temperature"] = serde_json::json!(temperature);
+    }
+    body
+}
+
+/// Builds an OpenAI Responses API request body: `input` items instead of
+/// `messages`, and `max_output_tokens` instead of `max_tokens`.
+fn responses_request_body(request: &CanonicalRequest) -> serde_json::Value {
+    let input: Vec<serde_json::Value> = request
+        .messages
+        .iter()
+        .map(|m| {
+            serde_json::json!({
+                "role": m.role.as_openai_str(),
+                "content": m.content,
+            })
+        })
+        .collect();
+
+    let mut body = serde_json::json!({
+        "model": request.model,
+        "input": input,
+        "stream": request.stream,
+    });
+    if let Some(max_output_tokens) = request.max_output_tokens {
+        body["max_output_tokens"] = serde_json::json!(max_output_tokens);
+    }
+    if let Some(temperature) = request.temperature {
+        body["temperature"] = serde_json::json!(temperature);
+    }
+    body
+}
+
+/// Builds a Google GenAI `generateContent`/`streamGenerateContent` request
+/// body. System messages move into the top-level `systemInstruction` field
+/// (Gemini has no `system` role in `contents`), and `assistant` becomes
+/// `model`.
+fn google_genai_request_body(request: &CanonicalRequest) -> serde_json::Value {
+    let mut system_parts = Vec::new();
+    let mut contents = Vec::new();
+
+    for message in &request.messages {
+        match message.role {
+            CanonicalRole::System => {
+                system_parts.push(serde_json::json!({ "text": message.content }));
+            }
+            CanonicalRole::User | CanonicalRole::Assistant => {
+                let role = if message.role == CanonicalRole::Assistant {
+                    "model"
+                } else {
+                    "user"
+                };
+                contents.push(serde_json::json!({
+                    "role": role,
+                    "parts": [{ "text": message.content }],
+                }));
+            }
+        }
+    }
+
+    let mut body = serde_json::json!({ "contents": contents });
+    if !system_parts.is_empty() {
+        body["systemInstruction"] = serde_json::json!({ "parts": system_parts });
+    }
+    if request.max_output_tokens.is_some() || request.temperature.is_some() {
+        let mut generation_config = serde_json::json!({});
+        if let Some(max_output_tokens) = request.max_output_tokens {
+            generation_config["maxOutputTokens"] = serde_json::json!(max_output_tokens);
+        }
+        if let Some(temperature) = request.temperature {
+            generation_config["temperature"] = serde_json::json!(temperature);
+        }
+        body["generationConfig"] = generation_config;
+    }
+    body
+}
+
+/// Builds an Anthropic Messages API request body. System messages are
+/// pulled out into the top-level `system` field, mirroring how Anthropic's
+/// wire format (unlike OpenAI's) has no `system` role inside `messages`.
+fn anthropic_messages_request_body(request: &CanonicalRequest) -> serde_json::Value {
+    let mut system = String::new();
+    let mut messages = Vec::new();
+
+    for message in &request.messages {
+        match message.role {
+            CanonicalRole::System => {
+                if !system.is_empty() {
+                    system.push('\n');
+                }
+                system.push_str(&message.content);
+            }
+            CanonicalRole::User | CanonicalRole::Assistant => {
+                messages.push(serde_json::json!({
+                    "role": message.role.as_openai_str(),
+                    "content": message.content,
+                }));
+            }
+        }
+    }
+
+    let mut body = serde_json::json!({
+        "model": request.model,
+        "messages": messages,
+        "stream": request.stream,
+        "max_tokens": request.max_output_tokens.unwrap_or(4096),
+    });
+    if !system.is_empty() {
+        body["system"] = serde_json::json!(system);
+    }
+    if let Some(temperature) = request.temperature {
+        body["temperature"] = serde_json::json!(temperature);
+    }
+    body
+}
+
+/// Builds a Bedrock `invoke-with-response-stream` request body. Bedrock
+/// embeds the selected model's own wire format in the POST body and, unlike
+/// an ordinary OpenAI-compatible endpoint, carries no top-level `model` or
+/// `stream` field of its own — the model is already named in the
+/// `/model/{model}/invoke-with-response-stream` URL, and streaming is
+/// already implied by that endpoint rather than a request flag. The most
+/// common Bedrock deployments front Anthropic Claude models, so this mirrors
+/// [`anthropic_messages_request_body`] minus those two fields, plus the
+/// `anthropic_version` field Bedrock's Claude invocations require in place
+/// of Anthropic's own `anthropic-version` header.
+fn bedrock_request_body(request: &CanonicalRequest) -> serde_json::Value {
+    let mut body = anthropic_messages_request_body(request);
+    if let Some(object) = body.as_object_mut() {
+        object.remove("model");
+        object.remove("stream");
+        object.insert(
+            "anthropic_version".to_string(),
+            serde_json::json!("bedrock-2023-05-31"),
+        );
+    }
+    body
+}
+
+/// Builds a Mistral FIM request body, flattening the message transcript down
+/// to a single `prompt` string since the FIM endpoint has no notion of
+/// conversational turns. Any `system` message content is prepended to the
+/// prompt; `assistant` content is dropped, as FIM backends complete a prompt
+/// rather than continue a dialogue. `suffix` carries the text after the
+/// cursor so the model can complete *between* prompt and suffix instead of
+/// only ever continuing past the end of the visible prefix.
+fn mistral_fim_request_body(request: &CanonicalRequest) -> serde_json::Value {
+    let prompt = request
+        .messages
+        .iter()
+        .filter(|m| m.role != CanonicalRole::Assistant)
+        .map(|m| m.content.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut body = serde_json::json!({
+        "model": request.model,
+        "prompt": prompt,
+        "stream": request.stream,
+    });
+    if let Some(suffix) = &request.suffix {
+        body["suffix"] = serde_json::json!(suffix);
+    }
+    if let Some(temperature) = request.temperature {
+        body["temperature"] = serde_json::json!(temperature);
+    }
+    if let Some(max_output_tokens) = request.max_output_tokens {
+        body["max_tokens"] = serde_json::json!(max_output_tokens);
+    }
+    body
+}
+
+/// Extracts the assistant's reply text from an OpenAI Chat Completions
+/// response: `choices[0].message.content`.
+fn extract_chat_completions_text(response: &serde_json::Value) -> Option<String> {
+    response
+        .get("choices")?
+        .get(0)?
+        .get("message")?
+        .get("content")?
+        .as_str()
+        .map(str::to_string)
+}
+
+/// Extracts the assistant's reply text from an OpenAI Responses API
+/// response: the first `output_text` item's `text`.
+fn extract_responses_text(response: &serde_json::Value) -> Option<String> {
+    response.get("output")?.as_array()?.iter().find_map(|item| {
+        if item.get("type")?.as_str()? != "output_text" {
+            return None;
+        }
+        item.get("text")?.as_str().map(str::to_string)
+    })
+}
+
+/// Extracts the assistant's reply text from a Google GenAI response:
+/// `candidates[0].content.parts[0].text`.
+fn extract_google_genai_text(response: &serde_json::Value) -> Option<String> {
+    response
+        .get("candidates")?
+        .get(0)?
+        .get("content")?
+        .get("parts")?
+        .get(0)?
+        .get("text")?
+        .as_str()
+        .map(str::to_string)
+}
+
+/// Extracts the assistant's reply text from an Anthropic Messages response:
+/// the first `text`-typed block in `content`.
+fn extract_anthropic_messages_text(response: &serde_json::Value) -> Option<String> {
+    response
+        .get("content")?
+        .as_array()?
+        .iter()
+        .find_map(|block| {
+            if block.get("type")?.as_str()? != "text" {
+                return None;
+            }
+            block.get("text")?.as_str().map(str::to_string)
+        })
+}
+
+/// Extracts the completion text from a Mistral FIM response:
+/// `choices[0].message.content`, the same shape Mistral's chat endpoint
+/// uses.
+fn extract_mistral_fim_text(response: &serde_json::Value) -> Option<String> {
+    extract_chat_completions_text(response)
+}
+
+/// Extracts the assistant's reply text from a Bedrock invocation response.
+/// Bedrock's unary response body is exactly the underlying model's own
+/// response shape; for the Claude-fronting deployments
+/// [`bedrock_request_body`] targets, that is Anthropic's own Messages
+/// response shape.
+fn extract_bedrock_text(response: &serde_json::Value) -> Option<String> {
+    extract_anthropic_messages_text(response)
+}
+
+/// Decodes one OpenAI Chat Completions (or Mistral FIM, which streams
+/// deltas in the same SSE shape) streaming event:
+/// `choices[0].delta.content`, with `choices[0].finish_reason` set to
+/// anything other than `null` marking the end of the stream.
+fn decode_chat_completions_stream_event(value: &serde_json::Value) -> Option<CanonicalStreamDelta> {
+    let choice = value.get("choices")?.get(0)?;
+    let text = choice
+        .get("delta")
+        .and_then(|delta| delta.get("content"))
+        .and_then(|c| c.as_str())
+        .map(str::to_string);
+    let done = choice
+        .get("finish_reason")
+        .map(|reason| !reason.is_null())
+        .unwrap_or(false);
+    Some(CanonicalStreamDelta { text, done })
+}
+
+/// Decodes one OpenAI Responses API streaming event. Only
+/// `response.output_text.delta` events carry text; a
+/// `response.completed` event marks the end of the stream.
+fn decode_responses_stream_event(value: &serde_json::Value) -> Option<CanonicalStreamDelta> {
+    let event_type = value.get("type")?.as_str()?;
+    match event_type {
+        "response.output_text.delta" => Some(CanonicalStreamDelta {
+            text: value.get("delta")?.as_str().map(str::to_string),
+            done: false,
+        }),
+        "response.completed" => Some(CanonicalStreamDelta {
+            text: None,
+            done: true,
+        }),
+        _ => None,
+    }
+}
+
+/// Decodes one Anthropic Messages (or Bedrock, which streams the same event
+/// shape for Claude models) streaming event. Only `content_block_delta`
+/// events with a `text_delta` carry text; `message_stop` marks the end of
+/// the stream.
+fn decode_anthropic_messages_stream_event(
+    value: &serde_json::Value,
+) -> Option<CanonicalStreamDelta> {
+    let event_type = value.get("type")?.as_str()?;
+    match event_type {
+        "content_block_delta" => Some(CanonicalStreamDelta {
+            text: value
+                .get("delta")
+                .and_then(|delta| delta.get("text"))
+                .and_then(|t| t.as_str())
+                .map(str::to_string),
+            done: false,
+        }),
+        "message_stop" => Some(CanonicalStreamDelta {
+            text: None,
+            done: true,
+        }),
+        _ => None,
+    }
 }
 
 const DEFAULT_OLLAMA_PORT: u32 = 11434;
 
 pub const BUILT_IN_OSS_MODEL_PROVIDER_ID: &str = "oss";
 pub const BUILT_IN_GOOGLE_GENAI_MODEL_PROVIDER_ID: &str = "google_genai";
+pub const BUILT_IN_GOOGLE_VERTEX_MODEL_PROVIDER_ID: &str = "google_vertex";
 pub const BUILT_IN_ANTHROPIC_MODEL_PROVIDER_ID: &str = "anthropic";
+pub const BUILT_IN_MISTRAL_FIM_MODEL_PROVIDER_ID: &str = "mistral_fim";
+pub const BUILT_IN_BEDROCK_MODEL_PROVIDER_ID: &str = "bedrock";
 
 /// Built-in default provider list.
 pub fn built_in_model_providers() -> HashMap<String, ModelProviderInfo> {
@@ -292,13 +1358,13 @@ pub fn built_in_model_providers() -> HashMap<String, ModelProviderInfo> {
             P {
                 name: "OpenAI".into(),
                 // Allow users to override the default OpenAI endpoint by
-                // exporting `OPENAI_BASE_URL`. This is useful when pointing
-                // Codex at a proxy, mock server, or Azure-style deployment
-                // without requiring a full TOML override for the built-in
-                // OpenAI provider.
-                base_url: std::env::var("OPENAI_BASE_URL")
-                    .ok()
-                    .filter(|v| !v.trim().is_empty()),
+                // exporting `OPENAI_BASE_URL` (or the uniform
+                // `CODEX_OPENAI_BASE_URL`/`CODEX_OPENAI_API_BASE`
+                // convention). This is useful when pointing Codex at a
+                // proxy, mock server, or Azure-style deployment without
+                // requiring a full TOML override for the built-in OpenAI
+                // provider.
+                base_url: base_url_override("openai", Some("OPENAI_BASE_URL")),
                 env_key: None,
                 env_key_instructions: None,
                 experimental_bearer_token: None,
@@ -325,6 +1391,38 @@ pub fn built_in_model_providers() -> HashMap<String, ModelProviderInfo> {
                 stream_max_retries: None,
                 stream_idle_timeout_ms: None,
                 requires_openai_auth: true,
+                auth: ProviderAuth::ApiKey,
+                proxy: None,
+                connect_timeout_ms: None,
+                url_params: None,
+                signing: None,
+                models: Some(vec![
+                    ModelInfo {
+                        id: "gpt-5".to_string(),
+                        max_input_tokens: Some(272_000),
+                        max_output_tokens: Some(128_000),
+                        supports_streaming: Some(true),
+                        supports_tools: Some(true),
+                        supports_vision: Some(true),
+                    },
+                    ModelInfo {
+                        id: "gpt-4o".to_string(),
+                        max_input_tokens: Some(128_000),
+                        max_output_tokens: Some(16_384),
+                        supports_streaming: Some(true),
+                        supports_tools: Some(true),
+                        supports_vision: Some(true),
+                    },
+                    ModelInfo {
+                        id: "o3".to_string(),
+                        max_input_tokens: Some(200_000),
+                        max_output_tokens: Some(100_000),
+                        supports_streaming: Some(true),
+                        supports_tools: Some(true),
+                        supports_vision: Some(true),
+                    },
+                ]),
+                key_selection: None,
             },
         ),
         (BUILT_IN_OSS_MODEL_PROVIDER_ID, create_oss_provider()),
@@ -332,16 +1430,66 @@ pub fn built_in_model_providers() -> HashMap<String, ModelProviderInfo> {
             BUILT_IN_GOOGLE_GENAI_MODEL_PROVIDER_ID,
             create_google_genai_provider(),
         ),
+        (
+            BUILT_IN_GOOGLE_VERTEX_MODEL_PROVIDER_ID,
+            create_google_vertex_provider(),
+        ),
         (
             BUILT_IN_ANTHROPIC_MODEL_PROVIDER_ID,
             create_anthropic_provider(),
         ),
+        (
+            BUILT_IN_MISTRAL_FIM_MODEL_PROVIDER_ID,
+            create_mistral_fim_provider(),
+        ),
+        (
+            BUILT_IN_BEDROCK_MODEL_PROVIDER_ID,
+            create_bedrock_provider(),
+        ),
     ]
     .into_iter()
     .map(|(k, v)| (k.to_string(), v))
     .collect()
 }
 
+/// A user-declared `model_providers` entry that doesn't match one of the
+/// hard-coded `create_*_provider` functions, as parsed from `config.toml`.
+/// Passed to [`create_generic_provider`] by
+/// [`built_in_model_providers_with_generic`].
+pub struct GenericProviderConfig {
+    /// This provider's `model_providers` key, e.g. `my-proxy`.
+    pub id: String,
+    pub name: String,
+    pub base_url: String,
+    pub wire_api: WireApi,
+    pub env_key: Option<String>,
+    pub extra_headers: Option<HashMap<String, String>>,
+}
+
+/// [`built_in_model_providers`]'s provider list, plus one
+/// [`create_generic_provider`] entry per `generic_providers`, so a user can
+/// point Codex at any OpenAI/Anthropic-compatible endpoint purely from
+/// `config.toml` without a hard-coded `create_*_provider` function. Later
+/// entries win if `generic_providers` reuses a built-in id.
+pub fn built_in_model_providers_with_generic(
+    generic_providers: &[GenericProviderConfig],
+) -> HashMap<String, ModelProviderInfo> {
+    let mut providers = built_in_model_providers();
+    for config in generic_providers {
+        providers.insert(
+            config.id.clone(),
+            create_generic_provider(
+                &config.name,
+                &config.base_url,
+                config.wire_api,
+                config.env_key.as_deref(),
+                config.extra_headers.clone(),
+            ),
+        );
+    }
+    providers
+}
+
 pub fn create_oss_provider() -> ModelProviderInfo {
     // These CODEX_OSS_ environment variables are experimental: we may
     // switch to reading values from config.toml instead.
@@ -378,6 +1526,13 @@ pub fn create_oss_provider_with_base_url(base_url: &str) -> ModelProviderInfo {
         stream_max_retries: None,
         stream_idle_timeout_ms: None,
         requires_openai_auth: false,
+        auth: ProviderAuth::ApiKey,
+        proxy: None,
+        connect_timeout_ms: None,
+        url_params: None,
+        signing: None,
+        models: None,
+        key_selection: None,
     }
 }
 
@@ -387,14 +1542,12 @@ pub fn create_oss_provider_with_base_url(base_url: &str) -> ModelProviderInfo {
 /// Authentication is via API key passed in the `x-goog-api-key` header.
 ///
 /// Environment variables:
-/// - `GOOGLE_GENAI_API_KEY`: Required API key for authentication
-/// - `GOOGLE_GENAI_BASE_URL`: Optional base URL override (defaults to generativelanguage.googleapis.com)
+/// - `GOOGLE_GENAI_API_KEY` (or `CODEX_GOOGLE_GENAI_API_KEY`): Required API key for authentication
+/// - `GOOGLE_GENAI_BASE_URL` (or `CODEX_GOOGLE_GENAI_BASE_URL`/`CODEX_GOOGLE_GENAI_API_BASE`): Optional base URL override (defaults to generativelanguage.googleapis.com)
 pub fn create_google_genai_provider() -> ModelProviderInfo {
     ModelProviderInfo {
         name: "Google GenAI".into(),
-        base_url: std::env::var("GOOGLE_GENAI_BASE_URL")
-            .ok()
-            .filter(|v| !v.trim().is_empty())
+        base_url: base_url_override("google_genai", Some("GOOGLE_GENAI_BASE_URL"))
             .or_else(|| Some("https://generativelanguage.googleapis.com/v1beta".to_string())),
         env_key: Some("GOOGLE_GENAI_API_KEY".into()),
         env_key_instructions: Some(
@@ -416,6 +1569,108 @@ pub fn create_google_genai_provider() -> ModelProviderInfo {
         stream_max_retries: None,
         stream_idle_timeout_ms: None,
         requires_openai_auth: false,
+        auth: ProviderAuth::ApiKey,
+        proxy: None,
+        connect_timeout_ms: None,
+        url_params: None,
+        signing: None,
+        models: Some(vec![
+            ModelInfo {
+                id: "gemini-2.5-pro".to_string(),
+                max_input_tokens: Some(1_048_576),
+                max_output_tokens: Some(65_536),
+                supports_streaming: Some(true),
+                supports_tools: Some(true),
+                supports_vision: Some(true),
+            },
+            ModelInfo {
+                id: "gemini-1.5-pro".to_string(),
+                max_input_tokens: Some(2_097_152),
+                max_output_tokens: Some(8_192),
+                supports_streaming: Some(true),
+                supports_tools: Some(true),
+                supports_vision: Some(true),
+            },
+            // Passthrough entry: accepts any Gemini model id this catalog
+            // doesn't list yet with the newest generally-available model's
+            // limits, rather than treating it as unsupported.
+            ModelInfo {
+                id: WILDCARD_MODEL_ID.to_string(),
+                max_input_tokens: Some(1_048_576),
+                max_output_tokens: Some(65_536),
+                supports_streaming: Some(true),
+                supports_tools: Some(true),
+                supports_vision: Some(true),
+            },
+        ]),
+        key_selection: None,
+    }
+}
+
+/// Creates a Google Vertex AI provider configuration.
+///
+/// Vertex AI speaks the same [`WireApi::GoogleGenAI`] request/response shape
+/// as the public Generative Language API, but lives at a project- and
+/// region-scoped URL and authenticates with a Google Cloud service-account
+/// key exchanged for an OAuth2 access token (see
+/// [`ModelProviderInfo::google_vertex_bearer_token`]) instead of a static
+/// `env_key`.
+///
+/// Environment variables:
+/// - `GOOGLE_APPLICATION_CREDENTIALS`: Required path to a service-account key file, unless overridden by `auth.key_path`
+/// - `GOOGLE_CLOUD_PROJECT`: Google Cloud project id, substituted into `{project_id}`. If unset, the
+///   `{project_id}` placeholder is left unresolved and `create_request_builder` returns a clear
+///   `CodexErr::EnvVar` error at request time rather than silently sending a malformed URL.
+/// - `GOOGLE_CLOUD_LOCATION`: Optional region (defaults to `us-central1`), substituted into `{location}`
+/// - `CODEX_GOOGLE_VERTEX_BASE_URL`/`CODEX_GOOGLE_VERTEX_API_BASE`: Optional base URL override
+pub fn create_google_vertex_provider() -> ModelProviderInfo {
+    let project_id = std::env::var("GOOGLE_CLOUD_PROJECT")
+        .ok()
+        .filter(|v| !v.trim().is_empty());
+    let location = std::env::var("GOOGLE_CLOUD_LOCATION")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| "us-central1".to_string());
+
+    ModelProviderInfo {
+        name: "Google Vertex AI".into(),
+        base_url: base_url_override("google_vertex", None).or_else(|| {
+            Some(
+                "https://{location}-aiplatform.googleapis.com/v1/projects/{project_id}/locations/{location}/publishers/google"
+                    .to_string(),
+            )
+        }),
+        env_key: None,
+        env_key_instructions: Some(
+            "Set the provider's auth.key_path, or point \
+             GOOGLE_APPLICATION_CREDENTIALS at a service-account key file; see \
+             https://cloud.google.com/vertex-ai/docs/authentication"
+                .into(),
+        ),
+        experimental_bearer_token: None,
+        wire_api: WireApi::GoogleGenAI,
+        query_params: None,
+        http_headers: None,
+        env_http_headers: None,
+        request_max_retries: None,
+        stream_max_retries: None,
+        stream_idle_timeout_ms: None,
+        requires_openai_auth: false,
+        auth: ProviderAuth::GoogleServiceAccount {
+            key_path: None,
+            scopes: Vec::new(),
+        },
+        proxy: None,
+        connect_timeout_ms: None,
+        url_params: Some(
+            [Some(("location".to_string(), location)), project_id.map(|p| ("project_id".to_string(), p))]
+                .into_iter()
+                .flatten()
+                .collect(),
+        ),
+        signing: None,
+        models: None,
+        key_selection: None,
     }
 }
 
@@ -426,14 +1681,12 @@ pub fn create_google_genai_provider() -> ModelProviderInfo {
 /// The API also requires an `anthropic-version` header for API versioning.
 ///
 /// Environment variables:
-/// - `ANTHROPIC_API_KEY`: Required API key for authentication
-/// - `ANTHROPIC_BASE_URL`: Optional base URL override (defaults to api.anthropic.com)
+/// - `ANTHROPIC_API_KEY` (or `CODEX_ANTHROPIC_API_KEY`): Required API key for authentication
+/// - `ANTHROPIC_BASE_URL` (or `CODEX_ANTHROPIC_BASE_URL`/`CODEX_ANTHROPIC_API_BASE`): Optional base URL override (defaults to api.anthropic.com)
 pub fn create_anthropic_provider() -> ModelProviderInfo {
     ModelProviderInfo {
         name: "Anthropic".into(),
-        base_url: std::env::var("ANTHROPIC_BASE_URL")
-            .ok()
-            .filter(|v| !v.trim().is_empty())
+        base_url: base_url_override("anthropic", Some("ANTHROPIC_BASE_URL"))
             .or_else(|| Some("https://api.anthropic.com/v1".to_string())),
         env_key: Some("ANTHROPIC_API_KEY".into()),
         env_key_instructions: Some(
@@ -456,9 +1709,289 @@ pub fn create_anthropic_provider() -> ModelProviderInfo {
         stream_max_retries: None,
         stream_idle_timeout_ms: None,
         requires_openai_auth: false,
+        auth: ProviderAuth::ApiKey,
+        proxy: None,
+        connect_timeout_ms: None,
+        url_params: None,
+        signing: None,
+        models: Some(vec![
+            ModelInfo {
+                id: "claude-opus-4-1".to_string(),
+                max_input_tokens: Some(200_000),
+                max_output_tokens: Some(32_000),
+                supports_streaming: Some(true),
+                supports_tools: Some(true),
+                supports_vision: Some(true),
+            },
+            ModelInfo {
+                id: "claude-sonnet-4-5".to_string(),
+                max_input_tokens: Some(200_000),
+                max_output_tokens: Some(64_000),
+                supports_streaming: Some(true),
+                supports_tools: Some(true),
+                supports_vision: Some(true),
+            },
+            // Claude 3 family, still widely pinned by existing configs.
+            ModelInfo {
+                id: "claude-3-opus-20240229".to_string(),
+                max_input_tokens: Some(200_000),
+                max_output_tokens: Some(4_096),
+                supports_streaming: Some(true),
+                supports_tools: Some(true),
+                supports_vision: Some(true),
+            },
+            ModelInfo {
+                id: "claude-3-sonnet-20240229".to_string(),
+                max_input_tokens: Some(200_000),
+                max_output_tokens: Some(4_096),
+                supports_streaming: Some(true),
+                supports_tools: Some(true),
+                supports_vision: Some(true),
+            },
+            ModelInfo {
+                id: "claude-3-haiku-20240307".to_string(),
+                max_input_tokens: Some(200_000),
+                max_output_tokens: Some(4_096),
+                supports_streaming: Some(true),
+                supports_tools: Some(true),
+                supports_vision: Some(true),
+            },
+            // Passthrough entry: accepts any Claude model id this catalog
+            // doesn't list yet with the current flagship's limits, rather
+            // than treating it as unsupported.
+            ModelInfo {
+                id: WILDCARD_MODEL_ID.to_string(),
+                max_input_tokens: Some(200_000),
+                max_output_tokens: Some(64_000),
+                supports_streaming: Some(true),
+                supports_tools: Some(true),
+                supports_vision: Some(true),
+            },
+        ]),
+        key_selection: None,
     }
 }
 
+/// Creates a Mistral provider configured for fill-in-the-middle completions.
+///
+/// Mistral's FIM endpoint takes a `prompt`/`suffix` pair rather than a
+/// message list, and returns a single completion (or streams deltas in the
+/// same SSE shape as chat), so it is registered under its own [`WireApi`]
+/// variant instead of [`WireApi::Chat`].
+///
+/// Environment variables:
+/// - `MISTRAL_API_KEY` (or `CODEX_MISTRAL_FIM_API_KEY`): Required API key for authentication
+/// - `CODEX_MISTRAL_FIM_BASE_URL`/`CODEX_MISTRAL_FIM_API_BASE`: Optional base URL override (defaults to api.mistral.ai)
+pub fn create_mistral_fim_provider() -> ModelProviderInfo {
+    ModelProviderInfo {
+        name: "Mistral FIM".into(),
+        base_url: base_url_override("mistral_fim", None)
+            .or_else(|| Some("https://api.mistral.ai/v1".to_string())),
+        env_key: Some("MISTRAL_API_KEY".into()),
+        env_key_instructions: Some(
+            "Get your API key from https://console.mistral.ai/api-keys".into(),
+        ),
+        experimental_bearer_token: None,
+        wire_api: WireApi::MistralFim,
+        query_params: None,
+        http_headers: None,
+        env_http_headers: None,
+        request_max_retries: None,
+        stream_max_retries: None,
+        stream_idle_timeout_ms: None,
+        requires_openai_auth: false,
+        auth: ProviderAuth::ApiKey,
+        proxy: None,
+        connect_timeout_ms: None,
+        url_params: None,
+        signing: None,
+        models: None,
+        key_selection: None,
+    }
+}
+
+/// Creates an AWS Bedrock provider configuration.
+///
+/// Bedrock rejects the `Authorization: Bearer` path entirely, so the
+/// provider sets [`ModelProviderInfo::signing`] to [`SigningScheme::SigV4`]
+/// instead of `env_key`; credentials are read from the standard
+/// `AWS_ACCESS_KEY_ID`/`AWS_SECRET_ACCESS_KEY`/`AWS_SESSION_TOKEN`
+/// environment variables at request time.
+///
+/// Environment variables:
+/// - `AWS_REGION`: Optional region override (defaults to `us-east-1`)
+/// - `CODEX_BEDROCK_BASE_URL`/`CODEX_BEDROCK_API_BASE`: Optional base URL
+///   override (defaults to `https://bedrock-runtime.{region}.amazonaws.com`)
+pub fn create_bedrock_provider() -> ModelProviderInfo {
+    let region = std::env::var("AWS_REGION")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .unwrap_or_else(|| "us-east-1".to_string());
+
+    ModelProviderInfo {
+        name: "AWS Bedrock".into(),
+        base_url: base_url_override("bedrock", None)
+            .or_else(|| Some("https://bedrock-runtime.{region}.amazonaws.com".to_string())),
+        env_key: None,
+        env_key_instructions: None,
+        experimental_bearer_token: None,
+        wire_api: WireApi::Bedrock,
+        query_params: None,
+        http_headers: None,
+        env_http_headers: None,
+        request_max_retries: None,
+        stream_max_retries: None,
+        stream_idle_timeout_ms: None,
+        requires_openai_auth: false,
+        auth: ProviderAuth::ApiKey,
+        proxy: None,
+        connect_timeout_ms: None,
+        url_params: Some(
+            [("region".to_string(), region.clone())]
+                .into_iter()
+                .collect(),
+        ),
+        signing: Some(SigningScheme::SigV4 {
+            service: "bedrock".to_string(),
+            region,
+        }),
+        models: None,
+        key_selection: None,
+    }
+}
+
+/// Builds a `ModelProviderInfo` for a self-hosted gateway or reverse proxy
+/// that exposes an OpenAI- or Anthropic-shaped API at an arbitrary
+/// `base_url`, without needing a bespoke `create_*_provider` function of its
+/// own. This is what config loading uses when a `model_providers` entry in
+/// `config.toml` supplies only the handful of fields a typical third-party
+/// endpoint needs; [`ModelProviderInfo::get_full_url`] still appends the
+/// right wire-api-specific path suffix (e.g. `/chat/completions` for
+/// [`WireApi::Chat`], `/messages` for [`WireApi::AnthropicMessages`]) and
+/// [`ModelProviderInfo::apply_http_headers`] still resolves `extra_headers`'
+/// env-var-valued entries from the environment exactly as it does for the
+/// built-in providers, so callers get the same behavior a hard-coded
+/// `create_*_provider` function would have produced.
+///
+/// `extra_headers` mirrors [`ModelProviderInfo::env_http_headers`]: each
+/// value names an environment variable to read at request time, not a
+/// literal header value, so secrets like an API key alias never need to be
+/// written into `config.toml` itself.
+pub fn create_generic_provider(
+    name: &str,
+    base_url: &str,
+    wire_api: WireApi,
+    env_key: Option<&str>,
+    extra_headers: Option<HashMap<String, String>>,
+) -> ModelProviderInfo {
+    ModelProviderInfo {
+        name: name.to_string(),
+        base_url: Some(base_url.to_string()),
+        env_key: env_key.map(str::to_string),
+        env_key_instructions: None,
+        experimental_bearer_token: None,
+        wire_api,
+        query_params: None,
+        http_headers: None,
+        env_http_headers: extra_headers,
+        request_max_retries: None,
+        stream_max_retries: None,
+        stream_idle_timeout_ms: None,
+        requires_openai_auth: false,
+        auth: ProviderAuth::ApiKey,
+        proxy: None,
+        connect_timeout_ms: None,
+        url_params: None,
+        signing: None,
+        models: None,
+        key_selection: None,
+    }
+}
+
+/// Returns the next index to use for [`KeySelectionMode::RoundRobin`] key
+/// selection on provider `id`, cycling through `0..len`.
+///
+/// Backed by a process-wide map of per-provider counters so consecutive
+/// requests to the same provider advance rather than repeat, even though
+/// `ModelProviderInfo` itself is cheap to clone and not carried across
+/// requests.
+fn next_round_robin_index(id: &str, len: usize) -> usize {
+    static COUNTERS: OnceLock<Mutex<HashMap<String, usize>>> = OnceLock::new();
+    let counters = COUNTERS.get_or_init(|| Mutex::new(HashMap::new()));
+    let mut counters = counters
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let counter = counters.entry(id.to_string()).or_insert(0);
+    let index = *counter % len;
+    *counter = (*counter + 1) % len;
+    index
+}
+
+/// Reads the convention-based override `CODEX_<ID>_<suffix>` for provider
+/// `id` (e.g. `convention_env_var("my-proxy", "BASE_URL")` reads
+/// `CODEX_MY_PROXY_BASE_URL`), returning `None` if unset or empty.
+///
+/// `id` is uppercased and any byte that isn't an ASCII letter/digit becomes
+/// `_`, so provider ids containing `-` or `.` still produce a valid
+/// environment variable name.
+fn convention_env_var(id: &str, suffix: &str) -> Option<String> {
+    let normalized_id: String = id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    std::env::var(format!("CODEX_{normalized_id}_{suffix}"))
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+}
+
+/// Resolves the effective base URL override for provider `id`, honoring, in
+/// order:
+///   1. `legacy_env_var` (e.g. `OPENAI_BASE_URL`), the provider's own
+///      historical override, if set.
+///   2. `CODEX_<ID>_BASE_URL`, then `CODEX_<ID>_API_BASE` — the uniform
+///      convention. Only the built-in `create_*_provider` constructors in
+///      this module call this function, so user-defined providers do not
+///      get this base-URL override.
+///
+/// Returns `None` if none of the above are set, in which case callers fall
+/// back to their hard-coded default. Config-file `base_url` overrides take
+/// precedence over all of this; they are applied by the `model_providers`
+/// merge step in config loading, outside this function.
+fn base_url_override(id: &str, legacy_env_var: Option<&str>) -> Option<String> {
+    legacy_env_var
+        .and_then(|var| std::env::var(var).ok().filter(|v| !v.trim().is_empty()))
+        .or_else(|| convention_env_var(id, "BASE_URL"))
+        .or_else(|| convention_env_var(id, "API_BASE"))
+}
+
+/// Returns an error naming the first unresolved `{placeholder}` left in
+/// `url` once [`ModelProviderInfo::substitute_url_params`] has run, fulfilling
+/// its doc comment's promise that callers can surface a clear error instead
+/// of silently sending a malformed request (e.g. a Vertex AI URL with a
+/// literal `{project_id}` segment when `GOOGLE_CLOUD_PROJECT` isn't set).
+fn require_resolved_url(url: &str) -> crate::error::Result<()> {
+    let Some(start) = url.find('{') else {
+        return Ok(());
+    };
+    let Some(len) = url[start..].find('}') else {
+        return Ok(());
+    };
+    let placeholder = &url[start + 1..start + len];
+    Err(crate::error::CodexErr::EnvVar(EnvVarError {
+        var: placeholder.to_ascii_uppercase(),
+        instructions: Some(format!(
+            "base_url has an unresolved `{{{placeholder}}}` placeholder; set url_params.{placeholder} \
+             (or the corresponding environment variable) and retry"
+        )),
+    }))
+}
+
 fn matches_azure_responses_base_url(base_url: &str) -> bool {
     let base = base_url.to_ascii_lowercase();
     const AZURE_MARKERS: [&str; 5] = [
@@ -471,6 +2004,310 @@ fn matches_azure_responses_base_url(base_url: &str) -> bool {
     AZURE_MARKERS.iter().any(|marker| base.contains(marker))
 }
 
+/// Google OAuth2 token endpoint used to exchange a signed service-account
+/// JWT assertion for a short-lived access token, per
+/// <https://developers.google.com/identity/protocols/oauth2/service-account>.
+const GOOGLE_OAUTH_TOKEN_URI: &str = "https://oauth2.googleapis.com/token";
+
+/// OAuth2 scope requested for Vertex AI access tokens.
+const VERTEX_AI_OAUTH_SCOPE: &str = "https://www.googleapis.com/auth/cloud-platform";
+
+/// Safety margin subtracted from a cached access token's expiry so a
+/// request doesn't start mid-flight with a token that expires before the
+/// response comes back.
+const GOOGLE_ACCESS_TOKEN_EXPIRY_MARGIN_SECS: u64 = 60;
+
+/// Parsed shape of a Google Cloud service-account key file, as downloaded
+/// from the Cloud Console. Only the fields Codex needs to mint a Vertex AI
+/// access token are modeled; the file also carries `type`, `project_id`,
+/// `private_key_id`, etc., which `serde` ignores since this isn't declared
+/// `deny_unknown_fields`.
+#[derive(Debug, Clone, Deserialize)]
+struct GoogleServiceAccountKey {
+    client_email: String,
+    private_key: String,
+    #[serde(default = "default_google_token_uri")]
+    token_uri: String,
+}
+
+fn default_google_token_uri() -> String {
+    GOOGLE_OAUTH_TOKEN_URI.to_string()
+}
+
+/// Claims of the self-signed JWT assertion a service account presents to
+/// [`GoogleServiceAccountKey::token_uri`] to request an access token.
+#[derive(Debug, Serialize)]
+struct ServiceAccountClaims {
+    iss: String,
+    scope: String,
+    aud: String,
+    iat: u64,
+    exp: u64,
+}
+
+/// The subset of Google's token-endpoint response Codex needs: the bearer
+/// token itself and its lifetime in seconds.
+#[derive(Debug, Deserialize)]
+struct GoogleTokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+/// Reads and parses the service-account key file at `key_path` (falling
+/// back to the `GOOGLE_APPLICATION_CREDENTIALS` environment variable, the
+/// standard Google Cloud client library convention, when `key_path` is
+/// unset), returning an error in the same
+/// [`crate::error::CodexErr::EnvVar`] shape used elsewhere in this module
+/// if no path is available or the file can't be read or parsed.
+fn load_google_service_account_key(
+    key_path: Option<&str>,
+) -> crate::error::Result<GoogleServiceAccountKey> {
+    let var = "GOOGLE_APPLICATION_CREDENTIALS";
+    let instructions = Some(
+        "Set the provider's auth.key_path, or point GOOGLE_APPLICATION_CREDENTIALS at a Google \
+         Cloud service-account key file; see \
+         https://cloud.google.com/docs/authentication/provide-credentials-adc"
+            .to_string(),
+    );
+
+    let path = key_path
+        .map(str::to_string)
+        .or_else(|| std::env::var(var).ok())
+        .filter(|v| !v.trim().is_empty())
+        .ok_or_else(|| {
+            crate::error::CodexErr::EnvVar(EnvVarError {
+                var: var.to_string(),
+                instructions: instructions.clone(),
+            })
+        })?;
+
+    let contents = std::fs::read_to_string(&path).map_err(|_| {
+        crate::error::CodexErr::EnvVar(EnvVarError {
+            var: var.to_string(),
+            instructions: instructions.clone(),
+        })
+    })?;
+
+    serde_json::from_str(&contents).map_err(|_| {
+        crate::error::CodexErr::EnvVar(EnvVarError {
+            var: var.to_string(),
+            instructions,
+        })
+    })
+}
+
+/// Builds and RS256-signs the JWT assertion exchanged for a Vertex AI access
+/// token, valid for one hour from `iat`. `scopes` are space-joined per the
+/// JWT-bearer spec; empty falls back to [`VERTEX_AI_OAUTH_SCOPE`].
+fn build_service_account_assertion(
+    key: &GoogleServiceAccountKey,
+    scopes: &[String],
+) -> crate::error::Result<String> {
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let scope = if scopes.is_empty() {
+        VERTEX_AI_OAUTH_SCOPE.to_string()
+    } else {
+        scopes.join(" ")
+    };
+    let claims = ServiceAccountClaims {
+        iss: key.client_email.clone(),
+        scope,
+        aud: key.token_uri.clone(),
+        iat: now,
+        exp: now + 3600,
+    };
+
+    let encoding_key = EncodingKey::from_rsa_pem(key.private_key.as_bytes()).map_err(|_| {
+        crate::error::CodexErr::EnvVar(EnvVarError {
+            var: "GOOGLE_APPLICATION_CREDENTIALS".to_string(),
+            instructions: Some(
+                "service-account private_key is not a valid PEM-encoded RSA key".to_string(),
+            ),
+        })
+    })?;
+
+    jwt_encode(&Header::new(Algorithm::RS256), &claims, &encoding_key).map_err(|_| {
+        crate::error::CodexErr::EnvVar(EnvVarError {
+            var: "GOOGLE_APPLICATION_CREDENTIALS".to_string(),
+            instructions: None,
+        })
+    })
+}
+
+/// Process-wide cache of `client_email` -> `(access_token, expires_at)` so
+/// consecutive Vertex AI requests reuse one token instead of minting a fresh
+/// one (and signing a new JWT) every call.
+fn google_access_token_cache() -> &'static Mutex<HashMap<String, (String, u64)>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, (String, u64)>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn cached_google_access_token(client_email: &str) -> Option<String> {
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let cache = google_access_token_cache()
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    cache
+        .get(client_email)
+        .filter(|(_, expires_at)| *expires_at > now + GOOGLE_ACCESS_TOKEN_EXPIRY_MARGIN_SECS)
+        .map(|(token, _)| token.clone())
+}
+
+fn cache_google_access_token(client_email: &str, access_token: &str, expires_in: u64) {
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let mut cache = google_access_token_cache()
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    cache.insert(
+        client_email.to_string(),
+        (access_token.to_string(), now + expires_in),
+    );
+}
+
+/// Reads an environment variable required for SigV4 signing, mapping a
+/// missing/empty value onto the same [`crate::error::CodexErr::EnvVar`]
+/// shape used by [`ModelProviderInfo::api_key`].
+fn read_required_aws_env(var: &str) -> crate::error::Result<String> {
+    std::env::var(var)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .ok_or_else(|| {
+            crate::error::CodexErr::EnvVar(EnvVarError {
+                var: var.to_string(),
+                instructions: None,
+            })
+        })
+}
+
+/// Splits a `https://host/path?query` URL into `(host, canonical_uri,
+/// canonical_query)` for SigV4 canonical-request construction, per
+/// <https://docs.aws.amazon.com/IAM/latest/UserGuide/create-signed-request.html#create-canonical-request>.
+/// `canonical_uri` always starts with `/` and has each path segment (not the
+/// separating `/`) percent-encoded, since model ids like
+/// `anthropic.claude-3-5-sonnet-20241022-v2:0` contain `:`, a reserved
+/// character AWS's server-side signature recomputation expects encoded as
+/// `%3A`. `canonical_query` has its keys/values percent-encoded and pairs
+/// sorted by key, per the same spec.
+fn split_url_for_signing(url: &str) -> (String, String, String) {
+    let without_scheme = url.split("://").nth(1).unwrap_or(url);
+    let (host, rest) = without_scheme
+        .split_once('/')
+        .unwrap_or((without_scheme, ""));
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let canonical_uri = sigv4_uri_encode(&format!("/{path}"), false);
+    let canonical_query = sigv4_canonical_query_string(query);
+    (host.to_string(), canonical_uri, canonical_query)
+}
+
+/// Percent-encodes `s` per SigV4's `UriEncode` (RFC 3986 unreserved
+/// characters -- `A-Za-z0-9-._~` -- pass through literally, everything else
+/// becomes an uppercase `%XX`). When `encode_slash` is false, `/` is also
+/// left literal, so a path can be encoded in one pass without being split
+/// into segments first.
+fn sigv4_uri_encode(s: &str, encode_slash: bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                out.push(byte as char);
+            }
+            b'/' if !encode_slash => out.push('/'),
+            _ => out.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    out
+}
+
+/// Canonicalizes a raw `key=value&key2=value2` query string for SigV4:
+/// percent-encodes each key/value and sorts pairs by (encoded key, encoded
+/// value), per the canonical-request spec referenced on
+/// [`split_url_for_signing`].
+fn sigv4_canonical_query_string(query: &str) -> String {
+    let mut pairs: Vec<(String, String)> = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((key, value)) => (sigv4_uri_encode(key, true), sigv4_uri_encode(value, true)),
+            None => (sigv4_uri_encode(pair, true), String::new()),
+        })
+        .collect();
+    pairs.sort();
+    pairs
+        .into_iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Returns `(YYYYMMDD, YYYYMMDDTHHMMSSZ)` for the current instant, the two
+/// timestamp forms SigV4 needs for the credential scope and the
+/// `x-amz-date` header respectively.
+fn amz_timestamp_now() -> (String, String) {
+    let secs = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let days = (secs / 86_400) as i64;
+    let time_of_day = secs % 86_400;
+    let (year, month, day) = civil_from_days(days);
+    let (hour, minute, second) = (
+        time_of_day / 3600,
+        (time_of_day / 60) % 60,
+        time_of_day % 60,
+    );
+
+    let date_stamp = format!("{year:04}{month:02}{day:02}");
+    let amz_date = format!("{date_stamp}T{hour:02}{minute:02}{second:02}Z");
+    (date_stamp, amz_date)
+}
+
+/// Converts a day count since the Unix epoch into `(year, month, day)`.
+/// Standard civil-from-days algorithm (Howard Hinnant), valid for the
+/// proleptic Gregorian calendar.
+fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z = z + 719_468;
+    let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
+    let doe = (z - era * 146_097) as u64;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let y = yoe as i64 + era * 400;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = if m <= 2 { y + 1 } else { y };
+    (y, m, d)
+}
+
+/// Hex-encodes `bytes` using lowercase digits, as required by SigV4's
+/// payload hash and final signature.
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Returns the hex-encoded SHA-256 digest of `data`.
+fn sha256_hex(data: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    hex_encode(&hasher.finalize())
+}
+
+/// Computes `HMAC-SHA256(key, data)`, used at each step of the SigV4
+/// signing-key derivation chain.
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -496,6 +2333,13 @@ base_url = "http://localhost:11434/v1"
             stream_max_retries: None,
             stream_idle_timeout_ms: None,
             requires_openai_auth: false,
+            auth: ProviderAuth::ApiKey,
+            proxy: None,
+            connect_timeout_ms: None,
+            url_params: None,
+            signing: None,
+            models: None,
+            key_selection: None,
         };
 
         let provider: ModelProviderInfo = toml::from_str(azure_provider_toml).unwrap();
@@ -526,6 +2370,13 @@ query_params = { api-version = "2025-04-01-preview" }
             stream_max_retries: None,
             stream_idle_timeout_ms: None,
             requires_openai_auth: false,
+            auth: ProviderAuth::ApiKey,
+            proxy: None,
+            connect_timeout_ms: None,
+            url_params: None,
+            signing: None,
+            models: None,
+            key_selection: None,
         };
 
         let provider: ModelProviderInfo = toml::from_str(azure_provider_toml).unwrap();
@@ -559,6 +2410,13 @@ env_http_headers = { "X-Example-Env-Header" = "EXAMPLE_ENV_VAR" }
             stream_max_retries: None,
             stream_idle_timeout_ms: None,
             requires_openai_auth: false,
+            auth: ProviderAuth::ApiKey,
+            proxy: None,
+            connect_timeout_ms: None,
+            url_params: None,
+            signing: None,
+            models: None,
+            key_selection: None,
         };
 
         let provider: ModelProviderInfo = toml::from_str(azure_provider_toml).unwrap();
@@ -582,6 +2440,13 @@ env_http_headers = { "X-Example-Env-Header" = "EXAMPLE_ENV_VAR" }
                 stream_max_retries: None,
                 stream_idle_timeout_ms: None,
                 requires_openai_auth: false,
+                auth: ProviderAuth::ApiKey,
+                proxy: None,
+                connect_timeout_ms: None,
+                url_params: None,
+                signing: None,
+                models: None,
+                key_selection: None,
             }
         }
 
@@ -615,6 +2480,13 @@ env_http_headers = { "X-Example-Env-Header" = "EXAMPLE_ENV_VAR" }
             stream_max_retries: None,
             stream_idle_timeout_ms: None,
             requires_openai_auth: false,
+            auth: ProviderAuth::ApiKey,
+            proxy: None,
+            connect_timeout_ms: None,
+            url_params: None,
+            signing: None,
+            models: None,
+            key_selection: None,
         };
         assert!(named_provider.is_azure_responses_endpoint());
 
@@ -697,21 +2569,58 @@ env_http_headers = { "X-Example-Env-Header" = "EXAMPLE_ENV_VAR" }
     #[test]
     fn test_url_construction_for_google_genai() {
         let provider = create_google_genai_provider();
-        let url = provider.get_full_url(&None);
+        let url = provider.get_full_url(&None, "gemini-1.5-pro");
 
         assert!(url.contains("generativelanguage.googleapis.com"));
-        assert!(url.contains("/v1beta/models/{model}:streamGenerateContent"));
+        assert!(url.contains("/v1beta/models/gemini-1.5-pro:streamGenerateContent"));
     }
 
     #[test]
     fn test_url_construction_for_anthropic() {
         let provider = create_anthropic_provider();
-        let url = provider.get_full_url(&None);
+        let url = provider.get_full_url(&None, "claude-3-opus");
 
         assert!(url.contains("api.anthropic.com"));
         assert!(url.ends_with("/messages"));
     }
 
+    #[test]
+    fn test_url_params_substitution_in_base_url() {
+        let provider = ModelProviderInfo {
+            name: "Cloudflare Workers AI".into(),
+            base_url: Some(
+                "https://api.cloudflare.com/client/v4/accounts/{account_id}/ai/v1".into(),
+            ),
+            env_key: None,
+            env_key_instructions: None,
+            experimental_bearer_token: None,
+            wire_api: WireApi::Chat,
+            query_params: None,
+            http_headers: None,
+            env_http_headers: None,
+            request_max_retries: None,
+            stream_max_retries: None,
+            stream_idle_timeout_ms: None,
+            requires_openai_auth: false,
+            auth: ProviderAuth::ApiKey,
+            proxy: None,
+            connect_timeout_ms: None,
+            url_params: Some(maplit::hashmap! {
+                "account_id".to_string() => "acct-123".to_string(),
+            }),
+            signing: None,
+            models: None,
+            key_selection: None,
+        };
+
+        let url = provider.get_full_url(&None, "llama-3");
+
+        assert_eq!(
+            url,
+            "https://api.cloudflare.com/client/v4/accounts/acct-123/ai/v1/chat/completions"
+        );
+    }
+
     #[test]
     fn test_built_in_providers_include_new_providers() {
         let providers = built_in_model_providers();
@@ -720,7 +2629,10 @@ env_http_headers = { "X-Example-Env-Header" = "EXAMPLE_ENV_VAR" }
         assert!(providers.contains_key("openai"));
         assert!(providers.contains_key("oss"));
         assert!(providers.contains_key("google_genai"));
+        assert!(providers.contains_key("google_vertex"));
         assert!(providers.contains_key("anthropic"));
+        assert!(providers.contains_key("mistral_fim"));
+        assert!(providers.contains_key("bedrock"));
 
         // Verify the new providers have correct wire_api
         assert_eq!(
@@ -731,6 +2643,12 @@ env_http_headers = { "X-Example-Env-Header" = "EXAMPLE_ENV_VAR" }
             providers.get("anthropic").unwrap().wire_api,
             WireApi::AnthropicMessages
         );
+        assert_eq!(
+            providers.get("mistral_fim").unwrap().wire_api,
+            WireApi::MistralFim
+        );
+        assert_eq!(providers.get("bedrock").unwrap().wire_api, WireApi::Bedrock);
+        assert!(providers.get("bedrock").unwrap().signing.is_some());
     }
 
     #[test]
@@ -749,6 +2667,14 @@ env_http_headers = { "X-Example-Env-Header" = "EXAMPLE_ENV_VAR" }
             serde_json::to_string(&WireApi::AnthropicMessages).unwrap(),
             "\"anthropic_messages\""
         );
+        assert_eq!(
+            serde_json::to_string(&WireApi::MistralFim).unwrap(),
+            "\"mistral_fim\""
+        );
+        assert_eq!(
+            serde_json::to_string(&WireApi::Bedrock).unwrap(),
+            "\"bedrock\""
+        );
     }
 
     #[test]
@@ -770,5 +2696,672 @@ env_http_headers = { "X-Example-Env-Header" = "EXAMPLE_ENV_VAR" }
             serde_json::from_str::<WireApi>("\"anthropic_messages\"").unwrap(),
             WireApi::AnthropicMessages
         );
+        assert_eq!(
+            serde_json::from_str::<WireApi>("\"mistral_fim\"").unwrap(),
+            WireApi::MistralFim
+        );
+        assert_eq!(
+            serde_json::from_str::<WireApi>("\"bedrock\"").unwrap(),
+            WireApi::Bedrock
+        );
+    }
+
+    #[test]
+    fn test_url_construction_for_mistral_fim() {
+        let provider = create_mistral_fim_provider();
+        let url = provider.get_full_url(&None, "codestral-latest");
+
+        assert!(url.contains("api.mistral.ai"));
+        assert!(url.ends_with("/fim/completions"));
+    }
+
+    #[test]
+    fn test_url_construction_for_bedrock() {
+        let provider = create_bedrock_provider();
+        let url = provider.get_full_url(&None, "anthropic.claude-3-sonnet");
+
+        assert!(url.contains("bedrock-runtime."));
+        assert!(url.contains(".amazonaws.com"));
+        assert!(url.ends_with("/model/anthropic.claude-3-sonnet/invoke-with-response-stream"));
+    }
+
+    #[test]
+    fn test_sha256_hex_known_vector() {
+        assert_eq!(
+            sha256_hex(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn test_split_url_for_signing() {
+        let (host, path, query) = split_url_for_signing(
+            "https://bedrock-runtime.us-east-1.amazonaws.com/model/foo/invoke?x=1",
+        );
+
+        assert_eq!(host, "bedrock-runtime.us-east-1.amazonaws.com");
+        assert_eq!(path, "/model/foo/invoke");
+        assert_eq!(query, "x=1");
+    }
+
+    #[test]
+    fn test_split_url_for_signing_percent_encodes_colon_in_model_id() {
+        let (host, path, query) = split_url_for_signing(
+            "https://bedrock-runtime.us-east-1.amazonaws.com/model/anthropic.claude-3-5-sonnet-20241022-v2:0/invoke-with-response-stream",
+        );
+
+        assert_eq!(host, "bedrock-runtime.us-east-1.amazonaws.com");
+        assert_eq!(
+            path,
+            "/model/anthropic.claude-3-5-sonnet-20241022-v2%3A0/invoke-with-response-stream"
+        );
+        assert_eq!(query, "");
+    }
+
+    #[test]
+    fn test_split_url_for_signing_sorts_and_encodes_query_pairs() {
+        let (_, _, query) =
+            split_url_for_signing("https://example.amazonaws.com/path?b=two words&a=1");
+
+        assert_eq!(query, "a=1&b=two%20words");
+    }
+
+    #[test]
+    fn test_deserialize_provider_with_proxy_and_connect_timeout() {
+        let toml_str = r#"
+name = "Corp Proxy"
+base_url = "https://example.com"
+proxy = "socks5://127.0.0.1:1080"
+connect_timeout_ms = 2000
+        "#;
+        let provider: ModelProviderInfo = toml::from_str(toml_str).unwrap();
+
+        assert_eq!(provider.proxy, Some("socks5://127.0.0.1:1080".to_string()));
+        assert_eq!(provider.effective_proxy(), provider.proxy);
+        assert_eq!(
+            provider.connect_timeout(),
+            Some(Duration::from_millis(2000))
+        );
+    }
+
+    #[test]
+    fn test_effective_proxy_falls_back_to_env() {
+        let provider = ModelProviderInfo {
+            name: "test".into(),
+            base_url: Some("https://example.com".into()),
+            env_key: None,
+            env_key_instructions: None,
+            experimental_bearer_token: None,
+            wire_api: WireApi::Chat,
+            query_params: None,
+            http_headers: None,
+            env_http_headers: None,
+            request_max_retries: None,
+            stream_max_retries: None,
+            stream_idle_timeout_ms: None,
+            requires_openai_auth: false,
+            auth: ProviderAuth::ApiKey,
+            proxy: None,
+            connect_timeout_ms: None,
+            url_params: None,
+            signing: None,
+            models: None,
+            key_selection: None,
+        };
+
+        unsafe {
+            std::env::set_var("HTTPS_PROXY", "http://proxy.example.com:8080");
+        }
+        assert_eq!(
+            provider.effective_proxy(),
+            Some("http://proxy.example.com:8080".to_string())
+        );
+        unsafe {
+            std::env::remove_var("HTTPS_PROXY");
+        }
+
+        assert_eq!(provider.connect_timeout(), None);
+    }
+
+    #[test]
+    fn test_convention_env_var_normalizes_id() {
+        let var = "CODEX_MY_PROXY_BASE_URL";
+        unsafe {
+            std::env::set_var(var, "https://proxy.example.com");
+        }
+
+        assert_eq!(
+            convention_env_var("my-proxy", "BASE_URL"),
+            Some("https://proxy.example.com".to_string())
+        );
+
+        unsafe {
+            std::env::remove_var(var);
+        }
+    }
+
+    #[test]
+    fn test_api_key_falls_back_to_convention_env_var() {
+        let var = "CODEX_TEST_PROVIDER_NO_ENV_KEY_API_KEY";
+        unsafe {
+            std::env::set_var(var, "convention-key");
+        }
+
+        let provider = ModelProviderInfo {
+            name: "Test Provider".into(),
+            base_url: Some("https://example.com".into()),
+            env_key: None,
+            env_key_instructions: None,
+            experimental_bearer_token: None,
+            wire_api: WireApi::Chat,
+            query_params: None,
+            http_headers: None,
+            env_http_headers: None,
+            request_max_retries: None,
+            stream_max_retries: None,
+            stream_idle_timeout_ms: None,
+            requires_openai_auth: false,
+            auth: ProviderAuth::ApiKey,
+            proxy: None,
+            connect_timeout_ms: None,
+            url_params: None,
+            signing: None,
+            models: None,
+            key_selection: None,
+        };
+
+        assert_eq!(
+            provider.api_key("test_provider_no_env_key").unwrap(),
+            Some("convention-key".to_string())
+        );
+
+        unsafe {
+            std::env::remove_var(var);
+        }
+    }
+
+    #[test]
+    fn test_base_url_override_prefers_legacy_env_var_over_convention() {
+        let legacy = "MISTRAL_FIM_TEST_BASE_URL";
+        let convention = "CODEX_TEST_PROVIDER_BASE_URL_BASE_URL";
+        unsafe {
+            std::env::set_var(legacy, "https://legacy.example.com");
+            std::env::set_var(convention, "https://convention.example.com");
+        }
+
+        assert_eq!(
+            base_url_override("test_provider_base_url", Some(legacy)),
+            Some("https://legacy.example.com".to_string())
+        );
+
+        unsafe {
+            std::env::remove_var(legacy);
+        }
+
+        assert_eq!(
+            base_url_override("test_provider_base_url", Some(legacy)),
+            Some("https://convention.example.com".to_string())
+        );
+
+        unsafe {
+            std::env::remove_var(convention);
+        }
+    }
+
+    fn sample_canonical_request() -> CanonicalRequest {
+        CanonicalRequest {
+            model: "test-model".to_string(),
+            messages: vec![
+                CanonicalMessage {
+                    role: CanonicalRole::System,
+                    content: "Be concise.".to_string(),
+                },
+                CanonicalMessage {
+                    role: CanonicalRole::User,
+                    content: "Hello".to_string(),
+                },
+            ],
+            max_output_tokens: Some(256),
+            stream: true,
+            suffix: None,
+            temperature: None,
+        }
+    }
+
+    #[test]
+    fn test_chat_completions_transcoding_round_trip() {
+        let provider = create_oss_provider_with_base_url("http://localhost:11434/v1");
+        let body = provider.to_wire_request_body(&sample_canonical_request());
+
+        assert_eq!(body["model"], "test-model");
+        assert_eq!(body["messages"][0]["role"], "system");
+        assert_eq!(body["messages"][1]["content"], "Hello");
+        assert_eq!(body["max_tokens"], 256);
+
+        let response = serde_json::json!({
+            "choices": [{ "message": { "content": "Hi there" } }],
+        });
+        assert_eq!(
+            provider.extract_canonical_text(&response),
+            Some("Hi there".to_string())
+        );
+    }
+
+    #[test]
+    fn test_responses_transcoding_round_trip() {
+        let mut provider = create_oss_provider_with_base_url("http://localhost:11434/v1");
+        provider.wire_api = WireApi::Responses;
+        let body = provider.to_wire_request_body(&sample_canonical_request());
+
+        assert_eq!(body["input"][1]["role"], "user");
+        assert_eq!(body["max_output_tokens"], 256);
+
+        let response = serde_json::json!({
+            "output": [{ "type": "output_text", "text": "Hi there" }],
+        });
+        assert_eq!(
+            provider.extract_canonical_text(&response),
+            Some("Hi there".to_string())
+        );
+    }
+
+    #[test]
+    fn test_google_genai_transcoding_pulls_out_system_instruction() {
+        let provider = create_google_genai_provider();
+        let body = provider.to_wire_request_body(&sample_canonical_request());
+
+        assert_eq!(body["systemInstruction"]["parts"][0]["text"], "Be concise.");
+        assert_eq!(body["contents"][0]["role"], "user");
+
+        let response = serde_json::json!({
+            "candidates": [{ "content": { "parts": [{ "text": "Hi there" }] } }],
+        });
+        assert_eq!(
+            provider.extract_canonical_text(&response),
+            Some("Hi there".to_string())
+        );
+    }
+
+    #[test]
+    fn test_anthropic_transcoding_pulls_out_system_field() {
+        let provider = create_anthropic_provider();
+        let body = provider.to_wire_request_body(&sample_canonical_request());
+
+        assert_eq!(body["system"], "Be concise.");
+        assert_eq!(body["messages"].as_array().unwrap().len(), 1);
+        assert_eq!(body["messages"][0]["role"], "user");
+
+        let response = serde_json::json!({
+            "content": [{ "type": "text", "text": "Hi there" }],
+        });
+        assert_eq!(
+            provider.extract_canonical_text(&response),
+            Some("Hi there".to_string())
+        );
+    }
+
+    #[test]
+    fn test_mistral_fim_transcoding_flattens_to_prompt() {
+        let provider = create_mistral_fim_provider();
+        let body = provider.to_wire_request_body(&sample_canonical_request());
+
+        assert_eq!(body["prompt"], "Be concise.\nHello");
+    }
+
+    #[test]
+    fn test_mistral_fim_transcoding_includes_suffix_and_temperature() {
+        let provider = create_mistral_fim_provider();
+        let mut request = sample_canonical_request();
+        request.suffix = Some("return result;\n}".to_string());
+        request.temperature = Some(0.2);
+
+        let body = provider.to_wire_request_body(&request);
+
+        assert_eq!(body["suffix"], "return result;\n}");
+        assert_eq!(body["temperature"], 0.2);
+        assert_eq!(body["max_tokens"], 256);
+    }
+
+    #[test]
+    fn test_bedrock_transcoding_uses_anthropic_shape_without_model_or_stream() {
+        let provider = create_bedrock_provider();
+        let body = provider.to_wire_request_body(&sample_canonical_request());
+
+        assert_eq!(body["anthropic_version"], "bedrock-2023-05-31");
+        assert_eq!(body["system"], "Be concise.");
+        assert_eq!(body["messages"][0]["role"], "user");
+        assert!(body.get("model").is_none());
+        assert!(body.get("stream").is_none());
+
+        let response = serde_json::json!({
+            "content": [{ "type": "text", "text": "Hi there" }],
+        });
+        assert_eq!(
+            provider.extract_canonical_text(&response),
+            Some("Hi there".to_string())
+        );
+    }
+
+    #[test]
+    fn test_decode_stream_event_chat_completions_delta() {
+        let provider = create_oss_provider_with_base_url("http://localhost:11434/v1");
+
+        let delta = provider
+            .decode_stream_event(r#"{"choices":[{"delta":{"content":"Hi"},"finish_reason":null}]}"#)
+            .unwrap();
+        assert_eq!(delta.text, Some("Hi".to_string()));
+        assert!(!delta.done);
+
+        let last = provider
+            .decode_stream_event(r#"{"choices":[{"delta":{},"finish_reason":"stop"}]}"#)
+            .unwrap();
+        assert_eq!(last.text, None);
+        assert!(last.done);
+
+        let sentinel = provider.decode_stream_event("[DONE]").unwrap();
+        assert!(sentinel.done);
+    }
+
+    #[test]
+    fn test_decode_stream_event_responses_delta() {
+        let mut provider = create_oss_provider_with_base_url("http://localhost:11434/v1");
+        provider.wire_api = WireApi::Responses;
+
+        let delta = provider
+            .decode_stream_event(r#"{"type":"response.output_text.delta","delta":"Hi"}"#)
+            .unwrap();
+        assert_eq!(delta.text, Some("Hi".to_string()));
+        assert!(!delta.done);
+
+        let done = provider
+            .decode_stream_event(r#"{"type":"response.completed"}"#)
+            .unwrap();
+        assert_eq!(done.text, None);
+        assert!(done.done);
+    }
+
+    #[test]
+    fn test_decode_stream_event_anthropic_and_bedrock_delta() {
+        for provider in [create_anthropic_provider(), create_bedrock_provider()] {
+            let delta = provider
+                .decode_stream_event(
+                    r#"{"type":"content_block_delta","delta":{"type":"text_delta","text":"Hi"}}"#,
+                )
+                .unwrap();
+            assert_eq!(delta.text, Some("Hi".to_string()));
+            assert!(!delta.done);
+
+            let done = provider
+                .decode_stream_event(r#"{"type":"message_stop"}"#)
+                .unwrap();
+            assert_eq!(done.text, None);
+            assert!(done.done);
+        }
+    }
+
+    #[test]
+    fn test_google_vertex_provider_requires_service_account_auth() {
+        let provider = create_google_vertex_provider();
+
+        assert!(matches!(
+            provider.auth,
+            ProviderAuth::GoogleServiceAccount { .. }
+        ));
+        assert_eq!(provider.wire_api, WireApi::GoogleGenAI);
+        assert_eq!(provider.env_key, None);
+    }
+
+    #[test]
+    fn test_load_google_service_account_key_errors_when_env_var_unset() {
+        let var = "GOOGLE_APPLICATION_CREDENTIALS";
+        unsafe {
+            std::env::remove_var(var);
+        }
+
+        assert!(load_google_service_account_key(None).is_err());
+    }
+
+    #[test]
+    fn test_google_access_token_cache_round_trips() {
+        let email = "codex-test@example.iam.gserviceaccount.com";
+        assert_eq!(cached_google_access_token(email), None);
+
+        cache_google_access_token(email, "test-token", 3600);
+        assert_eq!(
+            cached_google_access_token(email),
+            Some("test-token".to_string())
+        );
+
+        cache_google_access_token(email, "expired-token", 0);
+        assert_eq!(cached_google_access_token(email), None);
+    }
+
+    #[test]
+    fn test_model_info_falls_back_to_wildcard_entry() {
+        let provider = create_anthropic_provider();
+
+        assert_eq!(
+            provider.model_info("claude-3-opus-20240229").unwrap().id,
+            "claude-3-opus-20240229"
+        );
+
+        let unknown = provider.model_info("claude-5-nonexistent").unwrap();
+        assert_eq!(unknown.id, WILDCARD_MODEL_ID);
+        assert_eq!(
+            provider.max_input_tokens("claude-5-nonexistent"),
+            Some(200_000)
+        );
+    }
+
+    #[test]
+    fn test_model_info_returns_none_without_wildcard_entry() {
+        let provider = create_mistral_fim_provider();
+
+        assert!(provider.model_info("whatever").is_none());
+    }
+
+    #[test]
+    fn test_generic_provider_composes_chat_completions_url() {
+        let provider = create_generic_provider(
+            "My Proxy",
+            "https://proxy.example.com/v1",
+            WireApi::Chat,
+            Some("MY_PROXY_API_KEY"),
+            None,
+        );
+
+        let url = provider.get_full_url(&None, "llama-3");
+        assert_eq!(url, "https://proxy.example.com/v1/chat/completions");
+    }
+
+    #[test]
+    fn test_generic_provider_composes_anthropic_messages_url() {
+        let provider = create_generic_provider(
+            "My Claude Gateway",
+            "https://gateway.example.com",
+            WireApi::AnthropicMessages,
+            None,
+            None,
+        );
+
+        let url = provider.get_full_url(&None, "claude-3-opus");
+        assert_eq!(url, "https://gateway.example.com/messages");
+    }
+
+    #[test]
+    fn test_generic_provider_resolves_extra_headers_from_env() {
+        let var = "CODEX_GENERIC_PROVIDER_TEST_HEADER";
+        unsafe {
+            std::env::set_var(var, "header-value");
+        }
+
+        let provider = create_generic_provider(
+            "My Proxy",
+            "https://proxy.example.com/v1",
+            WireApi::Chat,
+            None,
+            Some(
+                [("X-Custom-Header".to_string(), var.to_string())]
+                    .into_iter()
+                    .collect(),
+            ),
+        );
+
+        let client = CodexHttpClient::new();
+        let builder = client.post("https://proxy.example.com/v1/chat/completions");
+        let builder = provider.apply_http_headers(builder, None);
+        let request = builder.build().unwrap();
+
+        assert_eq!(
+            request.headers().get("X-Custom-Header").unwrap(),
+            "header-value"
+        );
+
+        unsafe {
+            std::env::remove_var(var);
+        }
+    }
+
+    #[test]
+    fn test_built_in_model_providers_with_generic_merges_custom_entry() {
+        let generic = GenericProviderConfig {
+            id: "my-proxy".to_string(),
+            name: "My Proxy".to_string(),
+            base_url: "https://proxy.example.com/v1".to_string(),
+            wire_api: WireApi::Chat,
+            env_key: Some("MY_PROXY_API_KEY".to_string()),
+            extra_headers: None,
+        };
+
+        let providers = built_in_model_providers_with_generic(&[generic]);
+
+        let base = built_in_model_providers();
+        assert_eq!(providers.len(), base.len() + 1);
+        let custom = providers.get("my-proxy").unwrap();
+        assert_eq!(custom.name, "My Proxy");
+        assert_eq!(custom.env_key, Some("MY_PROXY_API_KEY".to_string()));
+        assert!(providers.contains_key(BUILT_IN_ANTHROPIC_MODEL_PROVIDER_ID));
+    }
+
+    #[test]
+    fn test_http_client_for_request_reuses_client_without_override() {
+        let provider =
+            create_generic_provider("Plain", "https://example.com", WireApi::Chat, None, None);
+        let client = CodexHttpClient::new();
+
+        assert!(provider.http_client_for_request(&client).is_ok());
+    }
+
+    #[test]
+    fn test_http_client_for_request_builds_dedicated_client_with_proxy_and_timeout() {
+        let provider = ModelProviderInfo {
+            proxy: Some("socks5://127.0.0.1:1".to_string()),
+            connect_timeout_ms: Some(50),
+            ..create_generic_provider("Proxied", "https://example.com", WireApi::Chat, None, None)
+        };
+        let client = CodexHttpClient::new();
+
+        assert!(provider.http_client_for_request(&client).is_ok());
+    }
+
+    #[test]
+    fn test_http_client_for_request_rejects_invalid_proxy_url() {
+        let provider = ModelProviderInfo {
+            proxy: Some("not a valid proxy url".to_string()),
+            ..create_generic_provider("Proxied", "https://example.com", WireApi::Chat, None, None)
+        };
+        let client = CodexHttpClient::new();
+
+        assert!(provider.http_client_for_request(&client).is_err());
+    }
+
+    #[test]
+    fn test_select_api_key_round_robin_cycles_deterministically() {
+        let provider = ModelProviderInfo {
+            key_selection: Some(KeySelectionMode::RoundRobin),
+            ..create_generic_provider(
+                "Multi Key",
+                "https://example.com",
+                WireApi::Chat,
+                None,
+                None,
+            )
+        };
+        let id = "test_select_api_key_round_robin_cycles_deterministically";
+
+        let picks: Vec<String> = (0..4)
+            .map(|_| provider.select_api_key(id, "key-0, key-1, key-2"))
+            .collect();
+
+        assert_eq!(
+            picks,
+            vec!["key-0", "key-1", "key-2", "key-0"]
+                .into_iter()
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_select_api_key_round_robin_tracks_each_provider_id_separately() {
+        let provider = ModelProviderInfo {
+            key_selection: Some(KeySelectionMode::RoundRobin),
+            ..create_generic_provider(
+                "Multi Key",
+                "https://example.com",
+                WireApi::Chat,
+                None,
+                None,
+            )
+        };
+        let id_a = "test_select_api_key_round_robin_tracks_each_provider_id_separately_a";
+        let id_b = "test_select_api_key_round_robin_tracks_each_provider_id_separately_b";
+
+        // Interleave calls across two provider ids; each id's counter should
+        // advance independently of the other's.
+        assert_eq!(provider.select_api_key(id_a, "key-0, key-1"), "key-0");
+        assert_eq!(provider.select_api_key(id_b, "key-0, key-1"), "key-0");
+        assert_eq!(provider.select_api_key(id_a, "key-0, key-1"), "key-1");
+        assert_eq!(provider.select_api_key(id_b, "key-0, key-1"), "key-1");
+    }
+
+    #[test]
+    fn test_select_api_key_random_stays_within_key_set() {
+        let provider = ModelProviderInfo {
+            key_selection: Some(KeySelectionMode::Random),
+            ..create_generic_provider(
+                "Multi Key",
+                "https://example.com",
+                WireApi::Chat,
+                None,
+                None,
+            )
+        };
+        let id = "test_select_api_key_random_stays_within_key_set";
+        let keys = ["key-0", "key-1", "key-2"];
+
+        for _ in 0..20 {
+            let picked = provider.select_api_key(id, "key-0, key-1, key-2");
+            assert!(keys.contains(&picked.as_str()));
+        }
+    }
+
+    #[test]
+    fn test_select_api_key_default_selection_mode_is_random_and_stays_within_key_set() {
+        let provider = create_generic_provider(
+            "Multi Key",
+            "https://example.com",
+            WireApi::Chat,
+            None,
+            None,
+        );
+        assert_eq!(provider.key_selection, None);
+        let id = "test_select_api_key_default_selection_mode_is_random_and_stays_within_key_set";
+        let keys = ["key-0", "key-1", "key-2"];
+
+        for _ in 0..20 {
+            let picked = provider.select_api_key(id, "key-0, key-1, key-2");
+            assert!(keys.contains(&picked.as_str()));
+        }
     }
 }